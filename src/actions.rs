@@ -1,46 +1,93 @@
-use crate::{network, util};
-use anchor_client::ClientError;
-use solana_client::rpc_client::RpcClient;
+use crate::{amount::Amount, fee, network, policy::Policy, util};
+use anchor_client::{ClientError, RequestBuilder};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use solana_client::{client_error::ClientErrorKind, rpc_client::RpcClient};
 use solana_sdk::{
     commitment_config::CommitmentConfig,
+    instruction::Instruction,
     pubkey::Pubkey,
     signature::Signature,
     signer::{keypair::Keypair, Signer},
     system_program,
 };
 
+// every action is sent through here so the fee policy sees the exact instructions, including
+// the compute budget instructions for the configured priority fee
+fn send(request: RequestBuilder, payer: &Pubkey) -> Result<Signature, ClientError> {
+    let mut request = request;
+    for ix in fee::priority_fee_ixs().map_err(policy_error)? {
+        request = request.instruction(ix);
+    }
+
+    let ixs = request.instructions()?;
+    Policy::from_env()
+        .and_then(|policy| policy.check_fee(payer, &ixs))
+        .map_err(policy_error)?;
+
+    request.send()
+}
+
+fn policy_error(err: anyhow::Error) -> ClientError {
+    ClientError::SolanaClientError(ClientErrorKind::Custom(err.to_string()).into())
+}
+
+pub fn setup_wedding_ix(creator: &Pubkey, partner0: &Pubkey, partner1: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: util::program_id(),
+        accounts: crypto_wedding::accounts::SetupWedding {
+            creator: creator.clone(),
+            user_partner0: partner0.clone(),
+            user_partner1: partner1.clone(),
+            wedding: util::find_wedding_pda(partner0, partner1),
+            partner0: util::find_partner_pda(partner0),
+            partner1: util::find_partner_pda(partner1),
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: crypto_wedding::instruction::SetupWedding {}.data(),
+    }
+}
+
 pub fn setup_wedding(
     creator: &Keypair,
     partner0: &Pubkey,
     partner1: &Pubkey,
 ) -> Result<Signature, ClientError> {
-    let p_partner0 = util::find_partner_pda(partner0);
-    let p_partner1 = util::find_partner_pda(partner1);
-    let p_wedding = util::find_wedding_pda(partner0, partner1);
-
     let prog = util::get_crypto_wedding(Some(creator));
-    let sig = prog
+    let request = prog
         .request()
-        .accounts(crypto_wedding::accounts::SetupWedding {
-            creator: creator.pubkey(),
-            user_partner0: partner0.clone(),
-            user_partner1: partner1.clone(),
-            wedding: p_wedding,
-            partner0: p_partner0,
-            partner1: p_partner1,
-            system_program: system_program::id(),
-        })
-        .args(crypto_wedding::instruction::SetupWedding {})
-        .signer(creator)
-        .send()?;
+        .instruction(setup_wedding_ix(&creator.pubkey(), partner0, partner1))
+        .signer(creator);
+    let sig = send(request, &creator.pubkey())?;
 
-    let rpc_client = RpcClient::new(network::RPC_URL);
+    let rpc_client = RpcClient::new(network::rpc_url());
     let latest = rpc_client.get_latest_blockhash()?;
     rpc_client.confirm_transaction_with_spinner(&sig, &latest, CommitmentConfig::confirmed())?;
 
     Ok(sig)
 }
 
+// `creator` is the wedding creator, who gets the wedding rent back
+pub fn cancel_wedding_ix(
+    user: &Pubkey,
+    creator: &Pubkey,
+    partner0: &Pubkey,
+    partner1: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: util::program_id(),
+        accounts: crypto_wedding::accounts::CancelWedding {
+            user: user.clone(),
+            creator: creator.clone(),
+            user_partner0: partner0.clone(),
+            user_partner1: partner1.clone(),
+            wedding: util::find_wedding_pda(partner0, partner1),
+        }
+        .to_account_metas(None),
+        data: crypto_wedding::instruction::CancelWedding {}.data(),
+    }
+}
+
 pub fn cancel_wedding(
     user: &Keypair,
     partner0: &Pubkey,
@@ -51,229 +98,319 @@ pub fn cancel_wedding(
     let p_wedding = util::find_wedding_pda(partner0, partner1);
     let state: crypto_wedding::state::Wedding = prog.account(p_wedding)?;
 
-    let sig = prog
+    let request = prog
         .request()
-        .accounts(crypto_wedding::accounts::CancelWedding {
-            user: user.pubkey(),
-            creator: state.creator,
-            user_partner0: partner0.clone(),
-            user_partner1: partner1.clone(),
-            wedding: p_wedding,
-        })
-        .args(crypto_wedding::instruction::CancelWedding {})
-        .signer(user)
-        .send()?;
-
-    let rpc_client = RpcClient::new(network::RPC_URL);
+        .instruction(cancel_wedding_ix(
+            &user.pubkey(),
+            &state.creator,
+            partner0,
+            partner1,
+        ))
+        .signer(user);
+    let sig = send(request, &user.pubkey())?;
+
+    let rpc_client = RpcClient::new(network::rpc_url());
     let latest = rpc_client.get_latest_blockhash()?;
     rpc_client.confirm_transaction_with_spinner(&sig, &latest, CommitmentConfig::confirmed())?;
 
     Ok(sig)
 }
 
+pub fn setup_partner_ix(user: &Pubkey, other: &Pubkey, name: &str, vows: &str) -> Instruction {
+    Instruction {
+        program_id: util::program_id(),
+        accounts: crypto_wedding::accounts::SetupPartner {
+            user: user.clone(),
+            other: other.clone(),
+            partner: util::find_partner_pda(user),
+            wedding: util::find_wedding_pda(user, other),
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: crypto_wedding::instruction::SetupPartner {
+            name: name.into(),
+            vows: vows.into(),
+        }
+        .data(),
+    }
+}
+
 pub fn setup_partner(
     user: &Keypair,
     other: &Pubkey,
     name: &str,
     vows: &str,
 ) -> Result<Signature, ClientError> {
-    let user_pub = user.pubkey();
-    let p_partner = util::find_partner_pda(&user_pub);
-    let p_wedding = util::find_wedding_pda(&user_pub, other);
-
     let prog = util::get_crypto_wedding(Some(user));
-    let sig = prog
+    let request = prog
         .request()
-        .accounts(crypto_wedding::accounts::SetupPartner {
-            user: user.pubkey(),
-            other: other.clone(),
-            partner: p_partner,
-            wedding: p_wedding,
-            system_program: system_program::ID,
-        })
-        .args(crypto_wedding::instruction::SetupPartner {
-            name: name.into(),
-            vows: vows.into(),
-        })
-        .signer(user)
-        .send()?;
+        .instruction(setup_partner_ix(&user.pubkey(), other, name, vows))
+        .signer(user);
+    let sig = send(request, &user.pubkey())?;
 
-    let rpc_client = RpcClient::new(network::RPC_URL);
+    let rpc_client = RpcClient::new(network::rpc_url());
     let latest = rpc_client.get_latest_blockhash()?;
     rpc_client.confirm_transaction_with_spinner(&sig, &latest, CommitmentConfig::confirmed())?;
 
     Ok(sig)
 }
 
-pub fn close_partner(user: &Keypair, other: &Pubkey) -> Result<Signature, ClientError> {
-    let user_pub = user.pubkey();
-    let p_partner = util::find_partner_pda(&user_pub);
-    let p_wedding = util::find_wedding_pda(&user_pub, other);
+pub fn close_partner_ix(user: &Pubkey, other: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: util::program_id(),
+        accounts: crypto_wedding::accounts::ClosePartner {
+            user: user.clone(),
+            other: other.clone(),
+            partner: util::find_partner_pda(user),
+            wedding: util::find_wedding_pda(user, other),
+        }
+        .to_account_metas(None),
+        data: crypto_wedding::instruction::ClosePartner {}.data(),
+    }
+}
 
+pub fn close_partner(user: &Keypair, other: &Pubkey) -> Result<Signature, ClientError> {
     let prog = util::get_crypto_wedding(Some(user));
-    let sig = prog
+    let request = prog
         .request()
-        .accounts(crypto_wedding::accounts::ClosePartner {
-            user: user.pubkey(),
-            other: other.clone(),
-            partner: p_partner,
-            wedding: p_wedding,
-        })
-        .args(crypto_wedding::instruction::ClosePartner {})
-        .signer(user)
-        .send()?;
-
-    let rpc_client = RpcClient::new(network::RPC_URL);
+        .instruction(close_partner_ix(&user.pubkey(), other))
+        .signer(user);
+    let sig = send(request, &user.pubkey())?;
+
+    let rpc_client = RpcClient::new(network::rpc_url());
     let latest = rpc_client.get_latest_blockhash()?;
     rpc_client.confirm_transaction_with_spinner(&sig, &latest, CommitmentConfig::confirmed())?;
 
     Ok(sig)
 }
 
+pub fn update_partner_ix(user: &Pubkey, other: &Pubkey, name: &str, vows: &str) -> Instruction {
+    Instruction {
+        program_id: util::program_id(),
+        accounts: crypto_wedding::accounts::UpdatePartner {
+            user: user.clone(),
+            other: other.clone(),
+            partner: util::find_partner_pda(user),
+            wedding: util::find_wedding_pda(user, other),
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: crypto_wedding::instruction::UpdatePartner {
+            name: name.to_string(),
+            vows: vows.to_string(),
+        }
+        .data(),
+    }
+}
+
 pub fn update_partner(
     user: &Keypair,
     other: &Pubkey,
     name: &str,
     vows: &str,
 ) -> Result<Signature, ClientError> {
-    let user_pub = user.pubkey();
-    let p_partner = util::find_partner_pda(&user_pub);
-    let p_wedding = util::find_wedding_pda(&user_pub, other);
-
     let prog = util::get_crypto_wedding(Some(user));
-    let sig = prog
+    let request = prog
         .request()
-        .accounts(crypto_wedding::accounts::UpdatePartner {
-            user: user.pubkey(),
-            other: other.clone(),
-            partner: p_partner,
-            wedding: p_wedding,
-            system_program: system_program::id(),
-        })
-        .args(crypto_wedding::instruction::UpdatePartner {
-            name: name.to_string(),
-            vows: vows.to_string(),
-        })
-        .signer(user)
-        .send()?;
+        .instruction(update_partner_ix(&user.pubkey(), other, name, vows))
+        .signer(user);
+    let sig = send(request, &user.pubkey())?;
 
-    let rpc_client = RpcClient::new(network::RPC_URL);
+    let rpc_client = RpcClient::new(network::rpc_url());
     let latest = rpc_client.get_latest_blockhash()?;
     rpc_client.confirm_transaction_with_spinner(&sig, &latest, CommitmentConfig::confirmed())?;
 
     Ok(sig)
 }
 
-pub fn update_name(user: &Keypair, other: &Pubkey, name: &str) -> Result<Signature, ClientError> {
-    let user_pub = user.pubkey();
-    let p_partner = util::find_partner_pda(&user_pub);
-    let p_wedding = util::find_wedding_pda(&user_pub, other);
-
-    let prog = util::get_crypto_wedding(Some(user));
-    let sig = prog
-        .request()
-        .accounts(crypto_wedding::accounts::UpdateName {
-            user: user.pubkey(),
+pub fn update_name_ix(user: &Pubkey, other: &Pubkey, name: &str) -> Instruction {
+    Instruction {
+        program_id: util::program_id(),
+        accounts: crypto_wedding::accounts::UpdateName {
+            user: user.clone(),
             other: other.clone(),
-            partner: p_partner,
-            wedding: p_wedding,
+            partner: util::find_partner_pda(user),
+            wedding: util::find_wedding_pda(user, other),
             system_program: system_program::id(),
-        })
-        .args(crypto_wedding::instruction::UpdateName {
+        }
+        .to_account_metas(None),
+        data: crypto_wedding::instruction::UpdateName {
             name: name.to_string(),
-        })
-        .signer(user)
-        .send()?;
+        }
+        .data(),
+    }
+}
+
+pub fn update_name(user: &Keypair, other: &Pubkey, name: &str) -> Result<Signature, ClientError> {
+    let prog = util::get_crypto_wedding(Some(user));
+    let request = prog
+        .request()
+        .instruction(update_name_ix(&user.pubkey(), other, name))
+        .signer(user);
+    let sig = send(request, &user.pubkey())?;
 
-    let rpc_client = RpcClient::new(network::RPC_URL);
+    let rpc_client = RpcClient::new(network::rpc_url());
     let latest = rpc_client.get_latest_blockhash()?;
     rpc_client.confirm_transaction_with_spinner(&sig, &latest, CommitmentConfig::confirmed())?;
 
     Ok(sig)
 }
 
+pub fn update_vows_ix(user: &Pubkey, other: &Pubkey, vows: &str) -> Instruction {
+    Instruction {
+        program_id: util::program_id(),
+        accounts: crypto_wedding::accounts::UpdateVows {
+            user: user.clone(),
+            other: other.clone(),
+            partner: util::find_partner_pda(user),
+            wedding: util::find_wedding_pda(user, other),
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: crypto_wedding::instruction::UpdateVows {
+            vows: vows.to_string(),
+        }
+        .data(),
+    }
+}
+
 pub fn update_vows(user: &Keypair, other: &Pubkey, vows: &str) -> Result<Signature, ClientError> {
+    update_vows_with_memo(user, other, vows, None)
+}
+
+// update_vows with an optional memo signed by the user in the same transaction
+pub fn update_vows_with_memo(
+    user: &Keypair,
+    other: &Pubkey,
+    vows: &str,
+    memo: Option<&str>,
+) -> Result<Signature, ClientError> {
     let user_pub = user.pubkey();
-    let p_partner = util::find_partner_pda(&user_pub);
-    let p_wedding = util::find_wedding_pda(&user_pub, other);
 
     let prog = util::get_crypto_wedding(Some(user));
-    let sig = prog
-        .request()
-        .accounts(crypto_wedding::accounts::UpdateVows {
-            user: user.pubkey(),
-            other: other.clone(),
-            partner: p_partner,
-            wedding: p_wedding,
-            system_program: system_program::id(),
-        })
-        .args(crypto_wedding::instruction::UpdateVows {
-            vows: vows.to_string(),
-        })
-        .signer(user)
-        .send()?;
+    let mut request = prog.request();
+    if let Some(memo) = memo {
+        request = request.instruction(spl_memo::build_memo(memo.as_bytes(), &[&user_pub]));
+    }
 
-    let rpc_client = RpcClient::new(network::RPC_URL);
+    let request = request
+        .instruction(update_vows_ix(&user_pub, other, vows))
+        .signer(user);
+    let sig = send(request, &user.pubkey())?;
+
+    let rpc_client = RpcClient::new(network::rpc_url());
     let latest = rpc_client.get_latest_blockhash()?;
     rpc_client.confirm_transaction_with_spinner(&sig, &latest, CommitmentConfig::confirmed())?;
 
     Ok(sig)
 }
 
-pub fn give_answer(user: &Keypair, other: &Pubkey, answer: bool) -> Result<Signature, ClientError> {
-    let user_pub = user.pubkey();
-    let p_partner = util::find_partner_pda(&user_pub);
-    let p_other_partner = util::find_partner_pda(other);
-    let p_wedding = util::find_wedding_pda(&user_pub, other);
+pub fn give_answer_ix(user: &Pubkey, other: &Pubkey, answer: bool) -> Instruction {
+    Instruction {
+        program_id: util::program_id(),
+        accounts: crypto_wedding::accounts::GiveAnswer {
+            user: user.clone(),
+            other: other.clone(),
+            partner: util::find_partner_pda(user),
+            other_partner: util::find_partner_pda(other),
+            wedding: util::find_wedding_pda(user, other),
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: crypto_wedding::instruction::GiveAnswer { answer }.data(),
+    }
+}
 
+pub fn give_answer(user: &Keypair, other: &Pubkey, answer: bool) -> Result<Signature, ClientError> {
     let prog = util::get_crypto_wedding(Some(user));
-    let sig = prog
+    let request = prog
         .request()
-        .accounts(crypto_wedding::accounts::GiveAnswer {
-            user: user.pubkey(),
-            other: other.clone(),
-            partner: p_partner,
-            other_partner: p_other_partner,
-            wedding: p_wedding,
-            system_program: system_program::id(),
-        })
-        .args(crypto_wedding::instruction::GiveAnswer { answer })
-        .signer(user)
-        .send()?;
+        .instruction(give_answer_ix(&user.pubkey(), other, answer))
+        .signer(user);
+    let sig = send(request, &user.pubkey())?;
 
-    let rpc_client = RpcClient::new(network::RPC_URL);
+    let rpc_client = RpcClient::new(network::rpc_url());
     let latest = rpc_client.get_latest_blockhash()?;
     rpc_client.confirm_transaction_with_spinner(&sig, &latest, CommitmentConfig::confirmed())?;
 
     Ok(sig)
 }
 
+// `creator` is the wedding creator, who gets the rent of every closed account back
+pub fn divorce_ix(user: &Pubkey, creator: &Pubkey, other: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: util::program_id(),
+        accounts: crypto_wedding::accounts::Divorce {
+            creator: creator.clone(),
+            user: user.clone(),
+            other: other.clone(),
+            partner: util::find_partner_pda(user),
+            other_partner: util::find_partner_pda(other),
+            wedding: util::find_wedding_pda(user, other),
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: crypto_wedding::instruction::Divorce {}.data(),
+    }
+}
+
 pub fn divorce(user: &Keypair, other: &Pubkey) -> Result<Signature, ClientError> {
-    let user_pub = user.pubkey();
-    let p_partner = util::find_partner_pda(&user_pub);
-    let p_other_partner = util::find_partner_pda(other);
-    let p_wedding = util::find_wedding_pda(&user_pub, other);
+    let p_wedding = util::find_wedding_pda(&user.pubkey(), other);
 
     let prog = util::get_crypto_wedding(None);
     let state: crypto_wedding::state::Wedding = prog.account(p_wedding)?;
 
     let prog = util::get_crypto_wedding(Some(user));
-    let sig = prog
+    let request = prog
         .request()
-        .accounts(crypto_wedding::accounts::Divorce {
-            creator: state.creator,
-            user: user.pubkey(),
-            other: other.clone(),
-            partner: p_partner,
-            other_partner: p_other_partner,
-            wedding: p_wedding,
-            system_program: system_program::id(),
-        })
-        .args(crypto_wedding::instruction::Divorce {})
-        .signer(user)
-        .send()?;
+        .instruction(divorce_ix(&user.pubkey(), &state.creator, other))
+        .signer(user);
+    let sig = send(request, &user.pubkey())?;
 
-    let rpc_client = RpcClient::new(network::RPC_URL);
+    let rpc_client = RpcClient::new(network::rpc_url());
+    let latest = rpc_client.get_latest_blockhash()?;
+    rpc_client.confirm_transaction_with_spinner(&sig, &latest, CommitmentConfig::confirmed())?;
+
+    Ok(sig)
+}
+
+// sets up the wedding and the signer's partner account in a single transaction. when `answer`
+// is true the signer also says yes in the same transaction.
+pub fn propose_ixs(
+    user: &Pubkey,
+    other: &Pubkey,
+    name: &str,
+    vows: &str,
+    answer: bool,
+) -> Vec<Instruction> {
+    let mut ixs = vec![
+        setup_wedding_ix(user, user, other),
+        setup_partner_ix(user, other, name, vows),
+    ];
+    if answer {
+        ixs.push(give_answer_ix(user, other, answer));
+    }
+
+    ixs
+}
+
+pub fn propose(
+    user: &Keypair,
+    other: &Pubkey,
+    name: &str,
+    vows: &str,
+    answer: bool,
+) -> Result<Signature, ClientError> {
+    let prog = util::get_crypto_wedding(Some(user));
+    let mut request = prog.request();
+    for ix in propose_ixs(&user.pubkey(), other, name, vows, answer) {
+        request = request.instruction(ix);
+    }
+
+    let request = request.signer(user);
+    let sig = send(request, &user.pubkey())?;
+
+    let rpc_client = RpcClient::new(network::rpc_url());
     let latest = rpc_client.get_latest_blockhash()?;
     rpc_client.confirm_transaction_with_spinner(&sig, &latest, CommitmentConfig::confirmed())?;
 
@@ -335,6 +472,37 @@ pub fn print_partner(partner: &Pubkey) {
     }
 }
 
+pub fn print_balances(
+    signer: &Pubkey,
+    partner0: &Pubkey,
+    partner1: &Pubkey,
+) -> Result<(), ClientError> {
+    let rpc_client = RpcClient::new(network::rpc_url());
+    let accounts = [
+        ("signer", signer.clone()),
+        ("partner0", partner0.clone()),
+        ("partner1", partner1.clone()),
+        ("wedding PDA", util::find_wedding_pda(partner0, partner1)),
+        ("partner0 PDA", util::find_partner_pda(partner0)),
+        ("partner1 PDA", util::find_partner_pda(partner1)),
+    ];
+
+    println!("---| balances |---");
+    for (label, account) in accounts.iter() {
+        let balance = rpc_client.get_balance(account)?;
+        println!(
+            "{}: {:?} {} ({} lamports)",
+            label,
+            account,
+            Amount::from_lamports(balance),
+            balance
+        );
+    }
+    println!("------------------");
+
+    Ok(())
+}
+
 pub fn watch_wedding(partner0: &Pubkey, partner1: &Pubkey) -> Result<(), ClientError> {
     // wedding storage fields
     let mut last_creator = None;
@@ -0,0 +1,146 @@
+use crate::util::LAMPORTS_PER_SOL;
+use std::{fmt, str::FromStr};
+
+// fraction digits of decimal SOL, one lamport is 10^-9 SOL
+const SOL_DECIMALS: u8 = 9;
+
+// an amount of lamports which displays and parses as decimal SOL
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount(pub u64);
+
+impl Amount {
+    pub fn from_lamports(lamports: u64) -> Amount {
+        Amount(lamports)
+    }
+
+    pub fn lamports(&self) -> u64 {
+        self.0
+    }
+
+    pub fn sol(sol: u64) -> Amount {
+        Amount(sol * LAMPORTS_PER_SOL)
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} SOL", format_decimal(self.0, SOL_DECIMALS))
+    }
+}
+
+// formats base units (lamports, token units) as a decimal with `decimals` fraction digits,
+// falling back to the raw units when the scale does not fit in a u64
+pub fn format_decimal(units: u64, decimals: u8) -> String {
+    let scale = match 10u64.checked_pow(decimals as u32) {
+        Some(scale) => scale,
+        None => return format!("{} base units", units),
+    };
+    let whole = units / scale;
+    let fraction = units % scale;
+
+    if fraction == 0 {
+        whole.to_string()
+    } else {
+        let fraction = format!("{:0width$}", fraction, width = decimals as usize);
+        format!("{}.{}", whole, fraction.trim_end_matches('0'))
+    }
+}
+
+// parses a decimal like "1.5" into base units with `decimals` fraction digits
+pub fn parse_decimal(text: &str, decimals: u8) -> Option<u64> {
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if (whole.is_empty() && fraction.is_empty())
+        || fraction.len() > decimals as usize
+        || !whole.chars().all(|c| c.is_ascii_digit())
+        || !fraction.chars().all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let whole: u64 = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().ok()?
+    };
+    let fraction: u64 = if decimals == 0 {
+        0
+    } else {
+        format!("{:0<width$}", fraction, width = decimals as usize)
+            .parse()
+            .ok()?
+    };
+
+    whole
+        .checked_mul(10u64.checked_pow(decimals as u32)?)?
+        .checked_add(fraction)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseAmountError(String);
+
+impl fmt::Display for ParseAmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid amount {:?}, expected SOL like 1.5 or lamports like 1500lamports",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseAmountError {}
+
+// parses decimal SOL ("1.5", "1.5 SOL") or whole lamports ("1500 lamports")
+impl FromStr for Amount {
+    type Err = ParseAmountError;
+
+    fn from_str(input: &str) -> Result<Amount, ParseAmountError> {
+        let err = || ParseAmountError(input.into());
+        let text = input.trim().to_lowercase();
+
+        if let Some(lamports) = text.strip_suffix("lamports") {
+            return lamports.trim().parse().map(Amount).map_err(|_| err());
+        }
+
+        let text = text.strip_suffix("sol").unwrap_or(&text).trim();
+
+        parse_decimal(text, SOL_DECIMALS)
+            .map(Amount)
+            .ok_or_else(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_decimal_forms() {
+        assert_eq!(parse_decimal("1.5", 9), Some(1_500_000_000));
+        assert_eq!(parse_decimal(".5", 9), Some(500_000_000));
+        assert_eq!(parse_decimal("1.", 9), Some(1_000_000_000));
+        assert_eq!(parse_decimal("7", 0), Some(7));
+        assert_eq!(parse_decimal(".", 9), None);
+        assert_eq!(parse_decimal("1.5a", 9), None);
+    }
+
+    #[test]
+    fn parse_decimal_rejects_too_many_fraction_digits() {
+        assert_eq!(parse_decimal("1.0000000001", 9), None);
+        assert_eq!(parse_decimal("1.5", 0), None);
+    }
+
+    #[test]
+    fn parse_decimal_rejects_overflow() {
+        assert_eq!(parse_decimal("18446744073.709551615", 9), Some(u64::MAX));
+        assert_eq!(parse_decimal("18446744073.709551616", 9), None);
+        assert_eq!(parse_decimal("99999999999999999999", 0), None);
+        assert_eq!(parse_decimal("1", 20), None);
+    }
+
+    #[test]
+    fn format_decimal_falls_back_to_raw_units() {
+        assert_eq!(format_decimal(1_500_000_000, 9), "1.5");
+        assert_eq!(format_decimal(42, 20), "42 base units");
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{actions, network, util};
+use anyhow::{bail, Result};
+use crypto_wedding::state::Status;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::{keypair::Keypair, Signer},
+};
+
+pub struct CeremonyStep {
+    pub description: String,
+    pub sig: Signature,
+    pub status: String,
+}
+
+pub struct CeremonyPartner<'a> {
+    pub keypair: &'a Keypair,
+    pub name: &'a str,
+    pub vows: &'a str,
+}
+
+// statuses are checked against the program's variants, the report keeps the debug output so it
+// matches what print-wedding shows
+fn check_status(
+    description: &str,
+    partner0: &Pubkey,
+    partner1: &Pubkey,
+    expected: &[Status],
+) -> Result<String> {
+    let wedding = actions::get_wedding_state(partner0, partner1)?;
+
+    if !expected.contains(&wedding.status) {
+        bail!(
+            "unexpected wedding status after {}: got {:?}, expected one of {:?}",
+            description,
+            wedding.status,
+            expected
+        );
+    }
+
+    Ok(format!("{:?}", wedding.status))
+}
+
+// runs the full wedding lifecycle using locally held keys for both partners. the wedding status
+// is checked after every step and the run stops at the first unexpected status.
+pub fn run_ceremony(
+    creator: &Keypair,
+    partner0: &CeremonyPartner,
+    partner1: &CeremonyPartner,
+) -> Result<Vec<CeremonyStep>> {
+    let partner0_pub = partner0.keypair.pubkey();
+    let partner1_pub = partner1.keypair.pubkey();
+    let mut steps = Vec::new();
+
+    println!("ceremony: setting up wedding...");
+    let sig = actions::setup_wedding(creator, &partner0_pub, &partner1_pub)?;
+    let description = "setup wedding";
+    let status = check_status(
+        description,
+        &partner0_pub,
+        &partner1_pub,
+        &[Status::Created],
+    )?;
+    steps.push(CeremonyStep {
+        description: description.into(),
+        sig,
+        status,
+    });
+
+    for (partner, other) in [(partner0, &partner1_pub), (partner1, &partner0_pub)] {
+        let partner_pub = partner.keypair.pubkey();
+        println!("ceremony: setting up partner {:?}...", partner_pub);
+        let sig = actions::setup_partner(partner.keypair, other, partner.name, partner.vows)?;
+        let description = format!("setup partner {}", partner_pub);
+        let status = check_status(
+            &description,
+            &partner0_pub,
+            &partner1_pub,
+            &[Status::Created],
+        )?;
+        steps.push(CeremonyStep {
+            description,
+            sig,
+            status,
+        });
+    }
+
+    println!("ceremony: partner0 {:?} says yes...", partner0_pub);
+    let sig = actions::give_answer(partner0.keypair, &partner1_pub, true)?;
+    let description = format!("answer yes {}", partner0_pub);
+    let status = check_status(
+        &description,
+        &partner0_pub,
+        &partner1_pub,
+        &[Status::Created, Status::PartnerAnswered],
+    )?;
+    steps.push(CeremonyStep {
+        description,
+        sig,
+        status,
+    });
+
+    println!("ceremony: partner1 {:?} says yes...", partner1_pub);
+    let sig = actions::give_answer(partner1.keypair, &partner0_pub, true)?;
+    let description = format!("answer yes {}", partner1_pub);
+    let status = check_status(
+        &description,
+        &partner0_pub,
+        &partner1_pub,
+        &[Status::Married],
+    )?;
+    steps.push(CeremonyStep {
+        description,
+        sig,
+        status,
+    });
+
+    Ok(steps)
+}
+
+pub fn print_report(partner0: &Pubkey, partner1: &Pubkey, steps: &[CeremonyStep]) {
+    println!("---| ceremony report |---");
+    println!("wedding: {:?}", util::find_wedding_pda(partner0, partner1));
+    println!("partner0: {:?}", partner0);
+    println!("partner1: {:?}", partner1);
+    for (i, step) in steps.iter().enumerate() {
+        println!("{}. {}", i + 1, step.description);
+        println!("   status: {}", step.status);
+        println!("   sig: {}", step.sig);
+        println!(
+            "   tx: {}",
+            util::get_tx_link(&step.sig, network::explorer_cluster().as_deref())
+        );
+    }
+    println!("-------------------------");
+}
@@ -0,0 +1,462 @@
+use crate::{
+    actions,
+    decode::WeddingInstruction,
+    guestbook::GuestbookEntry,
+    history::{self, FetchedTransaction},
+    network, qr, util,
+};
+use anyhow::Result;
+use clap::ValueEnum;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Format {
+    Svg,
+    Html,
+    Pdf,
+}
+
+impl Format {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Svg => "svg",
+            Format::Html => "html",
+            Format::Pdf => "pdf",
+        }
+    }
+}
+
+pub struct CertificatePartner {
+    pub user: Pubkey,
+    pub name: String,
+    pub vows: String,
+}
+
+pub struct Certificate {
+    pub wedding: Pubkey,
+    pub status: String,
+    pub partner0: CertificatePartner,
+    pub partner1: CertificatePartner,
+    pub married_at: Option<i64>,
+    pub marriage_sig: Option<Signature>,
+    pub cluster: String,
+    // verified guest book entries, empty unless a guest book is added
+    pub guestbook: Vec<GuestbookEntry>,
+}
+
+// the last successful "yes" is the transaction which completed the marriage
+fn find_marriage_tx<'a>(
+    wedding: &Pubkey,
+    history: &'a [FetchedTransaction],
+) -> Option<&'a FetchedTransaction> {
+    history.iter().rev().find(|fetched| {
+        !fetched.failed()
+            && fetched
+                .wedding_instructions(wedding)
+                .contains(&WeddingInstruction::GiveAnswer { answer: true })
+    })
+}
+
+pub fn build_certificate(partner0: &Pubkey, partner1: &Pubkey) -> Result<Certificate> {
+    let wedding = actions::get_wedding_state(partner0, partner1)?;
+    let state0 = actions::get_partner_state(partner0)?;
+    let state1 = actions::get_partner_state(partner1)?;
+    let history = history::get_history(partner0, partner1)?;
+    let marriage_tx = find_marriage_tx(&util::find_wedding_pda(partner0, partner1), &history);
+
+    Ok(Certificate {
+        wedding: util::find_wedding_pda(partner0, partner1),
+        status: format!("{:?}", wedding.status),
+        partner0: CertificatePartner {
+            user: partner0.clone(),
+            name: state0.name,
+            vows: state0.vows,
+        },
+        partner1: CertificatePartner {
+            user: partner1.clone(),
+            name: state1.name,
+            vows: state1.vows,
+        },
+        married_at: marriage_tx.and_then(|fetched| fetched.block_time),
+        marriage_sig: marriage_tx.map(|fetched| fetched.sig),
+        cluster: network::cluster_name(),
+        guestbook: Vec::new(),
+    })
+}
+
+impl Certificate {
+    pub fn wedding_link(&self) -> String {
+        util::get_account_link(&self.wedding, network::explorer_cluster().as_deref())
+    }
+
+    pub fn marriage_link(&self) -> Option<String> {
+        self.marriage_sig
+            .map(|sig| util::get_tx_link(&sig, network::explorer_cluster().as_deref()))
+    }
+
+    pub fn date(&self) -> String {
+        history::format_block_time(self.married_at)
+    }
+
+    // the certificate as plain lines, shared by the svg and pdf layouts
+    fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("{} & {}", self.partner0.name, self.partner1.name),
+            String::new(),
+            format!("status: {}", self.status),
+            format!("married: {}", self.date()),
+            format!("cluster: {}", self.cluster),
+            format!("wedding: {}", self.wedding),
+            String::new(),
+        ];
+
+        for partner in [&self.partner0, &self.partner1] {
+            lines.push(format!("{} ({})", partner.name, partner.user));
+            lines.extend(wrap_text(&format!("\"{}\"", partner.vows), 80));
+            lines.push(String::new());
+        }
+
+        if !self.guestbook.is_empty() {
+            lines.push("guest book".into());
+            lines.push(String::new());
+            for entry in &self.guestbook {
+                lines.extend(wrap_text(&format!("\"{}\"", entry.message), 80));
+                lines.push(format!("- {}", entry.author()));
+                lines.push(String::new());
+            }
+        }
+
+        lines.push(self.wedding_link());
+        if let Some(link) = self.marriage_link() {
+            lines.push(link);
+        }
+
+        lines
+    }
+}
+
+pub fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+// svg rects drawing a qr code for `data` as a `size` pixel square at (x, y)
+fn qr_rects(data: &str, x: usize, y: usize, size: usize) -> Result<String> {
+    let (width, modules) = qr::modules(data)?;
+    let module = size as f64 / width as f64;
+
+    let mut rects = format!(
+        "  <g fill=\"#000\"><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#fff\"/>\n",
+        x, y, size, size
+    );
+    for (i, dark) in modules.iter().enumerate() {
+        if *dark {
+            rects.push_str(&format!(
+                "    <rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\"/>\n",
+                x as f64 + (i % width) as f64 * module,
+                y as f64 + (i / width) as f64 * module,
+                module,
+                module
+            ));
+        }
+    }
+    rects.push_str("  </g>\n");
+
+    Ok(rects)
+}
+
+pub fn render_svg(certificate: &Certificate) -> Result<String> {
+    let lines = certificate.lines();
+    let height = 160 + lines.len() * 22;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"800\" height=\"{}\" viewBox=\"0 0 800 {}\">\n",
+        height, height
+    );
+    svg.push_str(&format!(
+        "  <rect x=\"10\" y=\"10\" width=\"780\" height=\"{}\" fill=\"#fffdf5\" stroke=\"#b8860b\" stroke-width=\"4\"/>\n",
+        height - 20
+    ));
+    svg.push_str("  <text x=\"400\" y=\"80\" text-anchor=\"middle\" font-family=\"serif\" font-size=\"36\">Certificate of Marriage</text>\n");
+    for (i, line) in lines.iter().enumerate() {
+        svg.push_str(&format!(
+            "  <text x=\"400\" y=\"{}\" text-anchor=\"middle\" font-family=\"serif\" font-size=\"{}\">{}</text>\n",
+            140 + i * 22,
+            if i == 0 { 24 } else { 14 },
+            escape_xml(line)
+        ));
+    }
+    svg.push_str(&qr_rects(&certificate.wedding_link(), 680, 20, 100)?);
+    svg.push_str("</svg>\n");
+
+    Ok(svg)
+}
+
+pub fn render_html(certificate: &Certificate) -> Result<String> {
+    let mut partners = String::new();
+    for partner in [&certificate.partner0, &certificate.partner1] {
+        partners.push_str(&format!(
+            "    <section>\n      <h2>{}</h2>\n      <p class=\"key\">{}</p>\n      <blockquote>{}</blockquote>\n    </section>\n",
+            escape_xml(&partner.name),
+            partner.user,
+            escape_xml(&partner.vows).replace('\n', "<br>")
+        ));
+    }
+
+    let mut guestbook = String::new();
+    if !certificate.guestbook.is_empty() {
+        guestbook.push_str("    <section>\n      <h2>Guest Book</h2>\n");
+        for entry in &certificate.guestbook {
+            guestbook.push_str(&format!(
+                "      <blockquote>{}</blockquote>\n      <p class=\"key\">{}</p>\n",
+                escape_xml(&entry.message).replace('\n', "<br>"),
+                escape_xml(&entry.author())
+            ));
+        }
+        guestbook.push_str("    </section>\n");
+    }
+
+    let marriage_link = match certificate.marriage_link() {
+        Some(link) => format!(
+            "      <li><a href=\"{}\">marriage transaction</a></li>\n",
+            escape_xml(&link)
+        ),
+        None => String::new(),
+    };
+
+    let qr_code = qr::render_svg_fragment(&certificate.wedding_link())?;
+
+    Ok(format!(
+        r#"<!DOCTYPE html>
+<html>
+  <head>
+    <meta charset="utf-8">
+    <title>Certificate of Marriage</title>
+    <style>
+      body {{ font-family: serif; background: #fffdf5; text-align: center; }}
+      main {{ border: 4px solid #b8860b; margin: 2em auto; max-width: 50em; padding: 2em; }}
+      .key {{ font-family: monospace; font-size: 0.8em; }}
+    </style>
+  </head>
+  <body>
+    <main>
+    <h1>Certificate of Marriage</h1>
+    <p>{} &amp; {}</p>
+    <p>status: {}<br>married: {}<br>cluster: {}</p>
+    <p class="key">wedding: {}</p>
+{}{}    <ul>
+      <li><a href="{}">wedding account</a></li>
+{}    </ul>
+    <div>{}</div>
+    </main>
+  </body>
+</html>
+"#,
+        escape_xml(&certificate.partner0.name),
+        escape_xml(&certificate.partner1.name),
+        escape_xml(&certificate.status),
+        escape_xml(&certificate.date()),
+        escape_xml(&certificate.cluster),
+        certificate.wedding,
+        partners,
+        guestbook,
+        escape_xml(&certificate.wedding_link()),
+        marriage_link,
+        qr_code
+    ))
+}
+
+// standard pdf fonts with WinAnsiEncoding cover latin-1, which is written as octal escapes.
+// anything outside latin-1 is replaced.
+fn escape_pdf(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '(' | ')' | '\\' => format!("\\{}", c),
+            c if c.is_ascii() && !c.is_ascii_control() => c.to_string(),
+            '\u{a0}'..='\u{ff}' => format!("\\{:03o}", c as u32),
+            _ => "?".into(),
+        })
+        .collect()
+}
+
+// pdf y runs bottom up, lines start below the title and continue on a new page once they reach
+// the bottom margin
+const PDF_FIRST_LINE_Y: i64 = 740;
+const PDF_PAGE_TOP_Y: i64 = 790;
+const PDF_BOTTOM_MARGIN: i64 = 50;
+const PDF_LINE_HEIGHT: i64 = 14;
+
+// content stream of each page
+fn pdf_pages(certificate: &Certificate) -> Result<Vec<String>> {
+    let mut first = String::from("BT /F1 28 Tf 140 790 Td (Certificate of Marriage) Tj ET\n");
+
+    // qr code for the wedding account in the top right corner of the first page
+    let (width, modules) = qr::modules(&certificate.wedding_link())?;
+    let module = 90.0 / width as f64;
+    first.push_str("0 g\n");
+    for (i, dark) in modules.iter().enumerate() {
+        if *dark {
+            first.push_str(&format!(
+                "{:.2} {:.2} {:.2} {:.2} re f\n",
+                480.0 + (i % width) as f64 * module,
+                830.0 - (i / width + 1) as f64 * module,
+                module,
+                module
+            ));
+        }
+    }
+
+    let mut pages = vec![first];
+    let mut y = PDF_FIRST_LINE_Y;
+    for (i, line) in certificate.lines().iter().enumerate() {
+        if y < PDF_BOTTOM_MARGIN {
+            pages.push(String::new());
+            y = PDF_PAGE_TOP_Y;
+        }
+        if let Some(page) = pages.last_mut() {
+            page.push_str(&format!(
+                "BT /F1 {} Tf 50 {} Td ({}) Tj ET\n",
+                if i == 0 { 18 } else { 10 },
+                y,
+                escape_pdf(line)
+            ));
+        }
+        y -= PDF_LINE_HEIGHT;
+    }
+
+    Ok(pages)
+}
+
+// a pdf using the built in Times font, written by hand to avoid a pdf dependency
+pub fn render_pdf(certificate: &Certificate) -> Result<Vec<u8>> {
+    let pages = pdf_pages(certificate)?;
+
+    // catalog, page tree and font come first, followed by a page and its content per page
+    let kids: Vec<String> = (0..pages.len())
+        .map(|i| format!("{} 0 R", 4 + i * 2))
+        .collect();
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Times-Roman /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    ];
+    for (i, content) in pages.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 842] /Contents {} 0 R /Resources << /Font << /F1 3 0 R >> >> >>",
+            5 + i * 2
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ));
+    }
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.push_str(&format!("{} 0 obj\n{}\nendobj\n", i + 1, object));
+    }
+
+    let xref = pdf.len();
+    pdf.push_str(&format!(
+        "xref\n0 {}\n0000000000 65535 f \n",
+        objects.len() + 1
+    ));
+    for offset in offsets {
+        pdf.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    pdf.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    ));
+
+    Ok(pdf.into_bytes())
+}
+
+pub fn render(certificate: &Certificate, format: Format) -> Result<Vec<u8>> {
+    match format {
+        Format::Svg => Ok(render_svg(certificate)?.into_bytes()),
+        Format::Html => Ok(render_html(certificate)?.into_bytes()),
+        Format::Pdf => render_pdf(certificate),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn certificate(vows: &str) -> Certificate {
+        Certificate {
+            wedding: Pubkey::new_unique(),
+            status: "Married".into(),
+            partner0: CertificatePartner {
+                user: Pubkey::new_unique(),
+                name: "José".into(),
+                vows: vows.into(),
+            },
+            partner1: CertificatePartner {
+                user: Pubkey::new_unique(),
+                name: "Ana".into(),
+                vows: "yes".into(),
+            },
+            married_at: None,
+            marriage_sig: None,
+            cluster: "devnet".into(),
+            guestbook: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn escape_pdf_encodes_latin1() {
+        assert_eq!(escape_pdf("José (1\\2)"), "Jos\\351 \\(1\\\\2\\)");
+        assert_eq!(escape_pdf("日"), "?");
+    }
+
+    #[test]
+    fn render_pdf_flows_onto_new_pages() {
+        let vows = vec!["a line of vows"; 80].join("\n");
+        let certificate = certificate(&vows);
+        assert!(certificate.lines().len() > 60);
+
+        let pdf = String::from_utf8(render_pdf(&certificate).unwrap()).unwrap();
+        let pages = pdf.matches("/Type /Page /Parent").count();
+        assert!(pages > 1);
+        assert!(pdf.contains(&format!("/Count {}", pages)));
+
+        // every line sits above the bottom margin of its page
+        for line in pdf.lines().filter(|line| line.starts_with("BT /F1")) {
+            let y: i64 = line.split_whitespace().nth(5).unwrap().parse().unwrap();
+            assert!(y >= PDF_BOTTOM_MARGIN, "{}", line);
+        }
+    }
+}
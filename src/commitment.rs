@@ -0,0 +1,292 @@
+use crate::{decode::WeddingInstruction, history, network, util};
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{json, Value};
+use solana_sdk::{
+    hash::{hashv, Hash},
+    pubkey::Pubkey,
+    signature::Signature,
+};
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+};
+
+// committed vows are stored on chain as this prefix followed by sha256(salt | vows)
+pub const COMMITMENT_PREFIX: &str = "commit1:";
+// the reveal transaction carries the salt in a memo next to the plaintext vows
+pub const REVEAL_MEMO_PREFIX: &str = "crypto-wedding reveal ";
+
+pub struct VowsCommitment {
+    pub partner: Pubkey,
+    pub wedding: Pubkey,
+    pub vows: String,
+    pub salt: [u8; 32],
+}
+
+fn hash_vows(salt: &[u8; 32], vows: &str) -> Hash {
+    hashv(&[salt, vows.as_bytes()])
+}
+
+pub fn is_commitment(vows: &str) -> bool {
+    vows.starts_with(COMMITMENT_PREFIX)
+}
+
+pub fn reveal_memo(salt: &[u8; 32]) -> String {
+    format!("{}{}", REVEAL_MEMO_PREFIX, bs58::encode(salt).into_string())
+}
+
+fn parse_reveal_memo(memo: &str) -> Option<[u8; 32]> {
+    let salt = bs58::decode(memo.strip_prefix(REVEAL_MEMO_PREFIX)?)
+        .into_vec()
+        .ok()?;
+
+    salt.try_into().ok()
+}
+
+impl VowsCommitment {
+    pub fn new(partner: &Pubkey, other: &Pubkey, vows: String) -> VowsCommitment {
+        VowsCommitment {
+            partner: *partner,
+            wedding: util::find_wedding_pda(partner, other),
+            vows,
+            salt: rand::random(),
+        }
+    }
+
+    // the value written to the vows field instead of the vows themselves
+    pub fn commitment(&self) -> String {
+        format!("{}{}", COMMITMENT_PREFIX, hash_vows(&self.salt, &self.vows))
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "partner": self.partner.to_string(),
+            "wedding": self.wedding.to_string(),
+            "vows": self.vows,
+            "salt": bs58::encode(&self.salt).into_string(),
+            "commitment": self.commitment(),
+        })
+    }
+
+    pub fn from_json(value: &Value) -> Result<VowsCommitment> {
+        let field = |key: &str| {
+            value
+                .get(key)
+                .and_then(Value::as_str)
+                .ok_or_else(|| anyhow!("vows commitment is missing {}", key))
+        };
+        let salt = bs58::decode(field("salt")?)
+            .into_vec()?
+            .try_into()
+            .map_err(|_| anyhow!("vows commitment salt must be 32 bytes"))?;
+
+        Ok(VowsCommitment {
+            partner: Pubkey::from_str(field("partner")?)?,
+            wedding: Pubkey::from_str(field("wedding")?)?,
+            vows: field("vows")?.to_string(),
+            salt,
+        })
+    }
+
+    // the file holds the secret vows and salt, so it is owner-only. an existing file may hold
+    // the salt of a commitment already on chain and is only replaced with `force`.
+    pub fn save(&self, path: &PathBuf, force: bool) -> Result<()> {
+        let mut options = OpenOptions::new();
+        options.write(true);
+        if force {
+            options.create(true).truncate(true);
+        } else {
+            options.create_new(true);
+        }
+        #[cfg(unix)]
+        options.mode(0o600);
+
+        let mut file = match options.open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => bail!(
+                "{} already holds a vows commitment, pass --force to replace it",
+                path.display()
+            ),
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to create {}", path.display()))
+            }
+        };
+        // a replaced file keeps its old permissions otherwise
+        #[cfg(unix)]
+        file.set_permissions(fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("failed to restrict {}", path.display()))?;
+
+        file.write_all(serde_json::to_string_pretty(&self.to_json())?.as_bytes())
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    pub fn load(path: &PathBuf) -> Result<VowsCommitment> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+
+        VowsCommitment::from_json(&serde_json::from_str(&text)?)
+    }
+}
+
+// the plaintext and salt stay next to the env file until they are revealed
+pub fn default_path(wedding: &Pubkey) -> PathBuf {
+    dirs::home_dir()
+        .unwrap()
+        .join(format!(".crypto_wedding_vows_{}.json", wedding))
+}
+
+fn instruction_vows(instruction: &WeddingInstruction) -> Option<&str> {
+    match instruction {
+        WeddingInstruction::SetupPartner { vows, .. }
+        | WeddingInstruction::UpdatePartner { vows, .. }
+        | WeddingInstruction::UpdateVows { vows } => Some(vows),
+        _ => None,
+    }
+}
+
+pub struct RevealCheck {
+    pub commitment: String,
+    pub commit_sig: Signature,
+    pub reveal_sig: Signature,
+    pub vows: String,
+    pub matches: bool,
+}
+
+// walks the wedding history for vows `partner` committed to and later revealed with a salt
+// memo, checking every reveal against the commitment before it
+pub fn verify_reveals(partner: &Pubkey, other: &Pubkey) -> Result<Vec<RevealCheck>> {
+    let mut checks = Vec::new();
+    let mut committed: Option<(String, Signature)> = None;
+    let wedding = util::find_wedding_pda(partner, other);
+
+    for fetched in history::get_history(partner, other)? {
+        if fetched.failed() || !fetched.signers().contains(partner) {
+            continue;
+        }
+
+        let salt = fetched
+            .memos()
+            .iter()
+            .find_map(|memo| parse_reveal_memo(memo));
+        for instruction in fetched.wedding_instructions(&wedding) {
+            let vows = match instruction_vows(&instruction) {
+                Some(vows) => vows,
+                None => continue,
+            };
+
+            if is_commitment(vows) {
+                committed = Some((vows.to_string(), fetched.sig));
+            } else if let (Some((commitment, commit_sig)), Some(salt)) = (&committed, &salt) {
+                checks.push(RevealCheck {
+                    commitment: commitment.clone(),
+                    commit_sig: *commit_sig,
+                    reveal_sig: fetched.sig,
+                    vows: vows.to_string(),
+                    matches: *commitment
+                        == format!("{}{}", COMMITMENT_PREFIX, hash_vows(salt, vows)),
+                });
+                committed = None;
+            }
+        }
+    }
+
+    Ok(checks)
+}
+
+pub fn check_revealable(commitment: &VowsCommitment, on_chain_vows: &str) -> Result<()> {
+    if on_chain_vows != commitment.commitment() {
+        bail!(
+            "on-chain vows {:?} are not the saved commitment {:?}",
+            on_chain_vows,
+            commitment.commitment()
+        );
+    }
+
+    Ok(())
+}
+
+pub fn print_reveal_checks(partner: &Pubkey, checks: &[RevealCheck]) {
+    let cluster = network::explorer_cluster();
+    println!("---| revealed vows |---");
+    println!("partner: {:?}", partner);
+    if checks.is_empty() {
+        println!("no revealed commitments found");
+    }
+    for check in checks {
+        println!(
+            "{}: {:?}",
+            if check.matches { "MATCHES" } else { "MISMATCH" },
+            check.vows
+        );
+        println!("  commitment: {}", check.commitment);
+        println!(
+            "  committed: {}",
+            util::get_tx_link(&check.commit_sig, cluster.as_deref())
+        );
+        println!(
+            "  revealed: {}",
+            util::get_tx_link(&check.reveal_sig, cluster.as_deref())
+        );
+    }
+    println!("-----------------------");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn temp_path() -> PathBuf {
+        env::temp_dir().join(format!(
+            "crypto_wedding_commitment_{:016x}.json",
+            rand::random::<u64>()
+        ))
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let saved = VowsCommitment::new(
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            "always".into(),
+        );
+        let path = temp_path();
+        saved.save(&path, false).unwrap();
+
+        #[cfg(unix)]
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+
+        let loaded = VowsCommitment::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.partner, saved.partner);
+        assert_eq!(loaded.wedding, saved.wedding);
+        assert_eq!(loaded.vows, saved.vows);
+        assert_eq!(loaded.salt, saved.salt);
+        assert_eq!(loaded.commitment(), saved.commitment());
+    }
+
+    #[test]
+    fn save_keeps_an_existing_commitment_unless_forced() {
+        let partner = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let first = VowsCommitment::new(&partner, &other, "first".into());
+        let second = VowsCommitment::new(&partner, &other, "second".into());
+        let path = temp_path();
+        first.save(&path, false).unwrap();
+
+        assert!(second.save(&path, false).is_err());
+        assert_eq!(VowsCommitment::load(&path).unwrap().vows, "first");
+
+        second.save(&path, true).unwrap();
+        let loaded = VowsCommitment::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.vows, "second");
+    }
+}
@@ -0,0 +1,303 @@
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, ToAccountMetas};
+use solana_sdk::{hash::hash, pubkey::Pubkey};
+use std::fmt;
+
+pub enum WeddingAccount {
+    Wedding(crypto_wedding::state::Wedding),
+    Partner(crypto_wedding::state::Partner),
+}
+
+// identifies the account type by its anchor discriminator, no network access needed
+pub fn decode_account(data: &[u8]) -> anyhow::Result<WeddingAccount> {
+    if let Ok(wedding) = crypto_wedding::state::Wedding::try_deserialize(&mut &data[..]) {
+        return Ok(WeddingAccount::Wedding(wedding));
+    }
+
+    if let Ok(partner) = crypto_wedding::state::Partner::try_deserialize(&mut &data[..]) {
+        return Ok(WeddingAccount::Partner(partner));
+    }
+
+    anyhow::bail!(
+        "data is not a crypto wedding Wedding or Partner account (discriminator: {:?})",
+        &data[..data.len().min(8)]
+    )
+}
+
+pub fn print_account(account: &WeddingAccount) {
+    match account {
+        WeddingAccount::Wedding(wedding) => {
+            println!("---| wedding state |---");
+            println!("creator: {:?}", wedding.creator);
+            println!("partner0: {:?}", wedding.partner0);
+            println!("partner1: {:?}", wedding.partner1);
+            println!("status: {:?}", wedding.status);
+            println!("-----------------------");
+        }
+        WeddingAccount::Partner(partner) => {
+            println!("---| partner state |---");
+            println!("wedding: {:?}", partner.wedding);
+            println!("user: {:?}", partner.user);
+            println!("name: {:?}", partner.name);
+            println!("vows: {:?}", partner.vows);
+            println!("answer: {:?}", partner.answer);
+            println!("-----------------------");
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WeddingInstruction {
+    SetupWedding,
+    CancelWedding,
+    SetupPartner { name: String, vows: String },
+    ClosePartner,
+    UpdatePartner { name: String, vows: String },
+    UpdateName { name: String },
+    UpdateVows { vows: String },
+    GiveAnswer { answer: bool },
+    Divorce,
+    Unknown { discriminator: Vec<u8> },
+}
+
+// anchor prefixes instruction data with the first 8 bytes of sha256("global:<instruction name>")
+pub fn sighash(name: &str) -> [u8; 8] {
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash(format!("global:{}", name).as_bytes()).to_bytes()[..8]);
+
+    discriminator
+}
+
+// placeholder key used to find out where each named field lands in the account metas
+fn marker(index: usize) -> Pubkey {
+    Pubkey::new_from_array([index as u8 + 1; 32])
+}
+
+// `accounts` must be built with `marker(i)` for the field called `names[i]`
+fn names_in_order(accounts: impl ToAccountMetas, names: &[&'static str]) -> Vec<&'static str> {
+    accounts
+        .to_account_metas(None)
+        .iter()
+        .map(|meta| {
+            (0..names.len())
+                .find(|i| marker(*i) == meta.pubkey)
+                .map(|i| names[i])
+                .unwrap_or("unknown")
+        })
+        .collect()
+}
+
+impl WeddingInstruction {
+    // names of the accounts this instruction expects, in the order they appear in the instruction
+    pub fn account_names(&self) -> Vec<&'static str> {
+        match self {
+            WeddingInstruction::SetupWedding => names_in_order(
+                crypto_wedding::accounts::SetupWedding {
+                    creator: marker(0),
+                    user_partner0: marker(1),
+                    user_partner1: marker(2),
+                    wedding: marker(3),
+                    partner0: marker(4),
+                    partner1: marker(5),
+                    system_program: marker(6),
+                },
+                &[
+                    "creator",
+                    "user_partner0",
+                    "user_partner1",
+                    "wedding",
+                    "partner0",
+                    "partner1",
+                    "system_program",
+                ],
+            ),
+            WeddingInstruction::CancelWedding => names_in_order(
+                crypto_wedding::accounts::CancelWedding {
+                    user: marker(0),
+                    creator: marker(1),
+                    user_partner0: marker(2),
+                    user_partner1: marker(3),
+                    wedding: marker(4),
+                },
+                &[
+                    "user",
+                    "creator",
+                    "user_partner0",
+                    "user_partner1",
+                    "wedding",
+                ],
+            ),
+            WeddingInstruction::SetupPartner { .. } => names_in_order(
+                crypto_wedding::accounts::SetupPartner {
+                    user: marker(0),
+                    other: marker(1),
+                    partner: marker(2),
+                    wedding: marker(3),
+                    system_program: marker(4),
+                },
+                &["user", "other", "partner", "wedding", "system_program"],
+            ),
+            WeddingInstruction::ClosePartner => names_in_order(
+                crypto_wedding::accounts::ClosePartner {
+                    user: marker(0),
+                    other: marker(1),
+                    partner: marker(2),
+                    wedding: marker(3),
+                },
+                &["user", "other", "partner", "wedding"],
+            ),
+            WeddingInstruction::UpdatePartner { .. } => names_in_order(
+                crypto_wedding::accounts::UpdatePartner {
+                    user: marker(0),
+                    other: marker(1),
+                    partner: marker(2),
+                    wedding: marker(3),
+                    system_program: marker(4),
+                },
+                &["user", "other", "partner", "wedding", "system_program"],
+            ),
+            WeddingInstruction::UpdateName { .. } => names_in_order(
+                crypto_wedding::accounts::UpdateName {
+                    user: marker(0),
+                    other: marker(1),
+                    partner: marker(2),
+                    wedding: marker(3),
+                    system_program: marker(4),
+                },
+                &["user", "other", "partner", "wedding", "system_program"],
+            ),
+            WeddingInstruction::UpdateVows { .. } => names_in_order(
+                crypto_wedding::accounts::UpdateVows {
+                    user: marker(0),
+                    other: marker(1),
+                    partner: marker(2),
+                    wedding: marker(3),
+                    system_program: marker(4),
+                },
+                &["user", "other", "partner", "wedding", "system_program"],
+            ),
+            WeddingInstruction::GiveAnswer { .. } => names_in_order(
+                crypto_wedding::accounts::GiveAnswer {
+                    user: marker(0),
+                    other: marker(1),
+                    partner: marker(2),
+                    other_partner: marker(3),
+                    wedding: marker(4),
+                    system_program: marker(5),
+                },
+                &[
+                    "user",
+                    "other",
+                    "partner",
+                    "other_partner",
+                    "wedding",
+                    "system_program",
+                ],
+            ),
+            WeddingInstruction::Divorce => names_in_order(
+                crypto_wedding::accounts::Divorce {
+                    creator: marker(0),
+                    user: marker(1),
+                    other: marker(2),
+                    partner: marker(3),
+                    other_partner: marker(4),
+                    wedding: marker(5),
+                    system_program: marker(6),
+                },
+                &[
+                    "creator",
+                    "user",
+                    "other",
+                    "partner",
+                    "other_partner",
+                    "wedding",
+                    "system_program",
+                ],
+            ),
+            WeddingInstruction::Unknown { .. } => Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WeddingInstruction::SetupWedding => "setup_wedding",
+            WeddingInstruction::CancelWedding => "cancel_wedding",
+            WeddingInstruction::SetupPartner { .. } => "setup_partner",
+            WeddingInstruction::ClosePartner => "close_partner",
+            WeddingInstruction::UpdatePartner { .. } => "update_partner",
+            WeddingInstruction::UpdateName { .. } => "update_name",
+            WeddingInstruction::UpdateVows { .. } => "update_vows",
+            WeddingInstruction::GiveAnswer { .. } => "give_answer",
+            WeddingInstruction::Divorce => "divorce",
+            WeddingInstruction::Unknown { .. } => "unknown",
+        }
+    }
+
+    pub fn decode(data: &[u8]) -> Result<WeddingInstruction, std::io::Error> {
+        if data.len() < 8 {
+            return Ok(WeddingInstruction::Unknown {
+                discriminator: data.to_vec(),
+            });
+        }
+
+        let (discriminator, mut args) = data.split_at(8);
+        let instruction = if discriminator == sighash("setup_wedding") {
+            WeddingInstruction::SetupWedding
+        } else if discriminator == sighash("cancel_wedding") {
+            WeddingInstruction::CancelWedding
+        } else if discriminator == sighash("setup_partner") {
+            let crypto_wedding::instruction::SetupPartner { name, vows } =
+                AnchorDeserialize::deserialize(&mut args)?;
+            WeddingInstruction::SetupPartner { name, vows }
+        } else if discriminator == sighash("close_partner") {
+            WeddingInstruction::ClosePartner
+        } else if discriminator == sighash("update_partner") {
+            let crypto_wedding::instruction::UpdatePartner { name, vows } =
+                AnchorDeserialize::deserialize(&mut args)?;
+            WeddingInstruction::UpdatePartner { name, vows }
+        } else if discriminator == sighash("update_name") {
+            let crypto_wedding::instruction::UpdateName { name } =
+                AnchorDeserialize::deserialize(&mut args)?;
+            WeddingInstruction::UpdateName { name }
+        } else if discriminator == sighash("update_vows") {
+            let crypto_wedding::instruction::UpdateVows { vows } =
+                AnchorDeserialize::deserialize(&mut args)?;
+            WeddingInstruction::UpdateVows { vows }
+        } else if discriminator == sighash("give_answer") {
+            let crypto_wedding::instruction::GiveAnswer { answer } =
+                AnchorDeserialize::deserialize(&mut args)?;
+            WeddingInstruction::GiveAnswer { answer }
+        } else if discriminator == sighash("divorce") {
+            WeddingInstruction::Divorce
+        } else {
+            WeddingInstruction::Unknown {
+                discriminator: discriminator.to_vec(),
+            }
+        };
+
+        Ok(instruction)
+    }
+}
+
+impl fmt::Display for WeddingInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WeddingInstruction::SetupPartner { name, vows }
+            | WeddingInstruction::UpdatePartner { name, vows } => {
+                write!(f, "{} name: {:?} vows: {:?}", self.name(), name, vows)
+            }
+            WeddingInstruction::UpdateName { name } => {
+                write!(f, "{} name: {:?}", self.name(), name)
+            }
+            WeddingInstruction::UpdateVows { vows } => {
+                write!(f, "{} vows: {:?}", self.name(), vows)
+            }
+            WeddingInstruction::GiveAnswer { answer } => {
+                write!(f, "{} answer: {:?}", self.name(), answer)
+            }
+            WeddingInstruction::Unknown { discriminator } => {
+                write!(f, "{} discriminator: {:?}", self.name(), discriminator)
+            }
+            _ => write!(f, "{}", self.name()),
+        }
+    }
+}
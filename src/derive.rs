@@ -0,0 +1,100 @@
+use crate::util;
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+
+pub struct WeddingDerivation {
+    pub program_id: Pubkey,
+    pub partner0: Pubkey,
+    pub partner1: Pubkey,
+    pub wedding: Pubkey,
+    pub bump: u8,
+}
+
+pub struct PartnerDerivation {
+    pub program_id: Pubkey,
+    pub user: Pubkey,
+    pub partner: Pubkey,
+    pub bump: u8,
+}
+
+pub fn derive_wedding(
+    partner_a: &Pubkey,
+    partner_b: &Pubkey,
+    program_id: &Pubkey,
+) -> WeddingDerivation {
+    let (partner0, partner1) = crypto_wedding::util::sort_pubkeys(partner_a, partner_b);
+    let (wedding, bump) = util::find_wedding_pda_with_bump(partner_a, partner_b, program_id);
+
+    WeddingDerivation {
+        program_id: program_id.clone(),
+        partner0: partner0.clone(),
+        partner1: partner1.clone(),
+        wedding,
+        bump,
+    }
+}
+
+pub fn derive_partner(user: &Pubkey, program_id: &Pubkey) -> PartnerDerivation {
+    let (partner, bump) = util::find_partner_pda_with_bump(user, program_id);
+
+    PartnerDerivation {
+        program_id: program_id.clone(),
+        user: user.clone(),
+        partner,
+        bump,
+    }
+}
+
+impl WeddingDerivation {
+    pub fn to_json(&self) -> serde_json::Value {
+        let (partner0_pda, _) = util::find_partner_pda_with_bump(&self.partner0, &self.program_id);
+        let (partner1_pda, _) = util::find_partner_pda_with_bump(&self.partner1, &self.program_id);
+
+        json!({
+            "programId": self.program_id.to_string(),
+            "wedding": self.wedding.to_string(),
+            "bump": self.bump,
+            "partner0": self.partner0.to_string(),
+            "partner1": self.partner1.to_string(),
+            "seeds": ["wedding", self.partner0.to_string(), self.partner1.to_string()],
+            "partner0Pda": partner0_pda.to_string(),
+            "partner1Pda": partner1_pda.to_string(),
+        })
+    }
+
+    pub fn print(&self) {
+        println!("---| wedding PDA |---");
+        println!("program id: {:?}", self.program_id);
+        println!("wedding: {:?}", self.wedding);
+        println!("bump: {}", self.bump);
+        println!("partner0 (sorted): {:?}", self.partner0);
+        println!("partner1 (sorted): {:?}", self.partner1);
+        println!(
+            "seeds: [\"wedding\", {:?}, {:?}]",
+            self.partner0, self.partner1
+        );
+        println!("---------------------");
+    }
+}
+
+impl PartnerDerivation {
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "programId": self.program_id.to_string(),
+            "partner": self.partner.to_string(),
+            "bump": self.bump,
+            "user": self.user.to_string(),
+            "seeds": ["partner", self.user.to_string()],
+        })
+    }
+
+    pub fn print(&self) {
+        println!("---| partner PDA |---");
+        println!("program id: {:?}", self.program_id);
+        println!("partner: {:?}", self.partner);
+        println!("bump: {}", self.bump);
+        println!("user: {:?}", self.user);
+        println!("seeds: [\"partner\", {:?}]", self.user);
+        println!("---------------------");
+    }
+}
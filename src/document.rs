@@ -0,0 +1,105 @@
+use crate::{actions, history, network, transfer, util};
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::{keypair::Keypair, Signer},
+};
+use std::{fs, path::PathBuf};
+
+pub const DOCUMENT_MEMO_PREFIX: &str = "crypto-wedding document ";
+
+// hex sha256 of the file, the same as `sha256sum` prints
+pub fn hash_file(path: &PathBuf) -> Result<String> {
+    let bytes = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+
+    Ok(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+pub fn document_memo(wedding: &Pubkey, hash: &str) -> String {
+    format!("{}{} sha256:{}", DOCUMENT_MEMO_PREFIX, wedding, hash)
+}
+
+// only a partner of an existing wedding can anchor a document in its history
+pub fn anchor_document(signer: &Keypair, other: &Pubkey, hash: &str) -> Result<Signature> {
+    let signer_pub = signer.pubkey();
+    let state = actions::get_wedding_state(&signer_pub, other)?;
+    if signer_pub != state.partner0 && signer_pub != state.partner1 {
+        bail!("{} is not a partner of this wedding", signer_pub);
+    }
+    let wedding = util::find_wedding_pda(&signer_pub, other);
+
+    transfer::send_memo(signer, &wedding, &document_memo(&wedding, hash))
+}
+
+pub struct Anchoring {
+    pub sig: Signature,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub signers: Vec<Pubkey>,
+    // whether one of the partners signed the memo
+    pub by_partner: bool,
+}
+
+// every successful transaction in the wedding history carrying the memo for `hash`, oldest
+// first. anyone can send the memo, so anchorings not signed by a partner are flagged.
+pub fn find_anchorings(partner0: &Pubkey, partner1: &Pubkey, hash: &str) -> Result<Vec<Anchoring>> {
+    let memo = document_memo(&util::find_wedding_pda(partner0, partner1), hash);
+
+    Ok(history::get_history(partner0, partner1)?
+        .iter()
+        .filter(|fetched| !fetched.failed() && fetched.memos().contains(&memo))
+        .map(|fetched| Anchoring {
+            sig: fetched.sig,
+            slot: fetched.slot,
+            block_time: fetched.block_time,
+            signers: fetched.signers().to_vec(),
+            by_partner: fetched
+                .signers()
+                .iter()
+                .any(|signer| signer == partner0 || signer == partner1),
+        })
+        .collect())
+}
+
+// fails unless a partner anchored the document, so scripts can tell a real anchoring from a memo
+// anyone could have sent
+pub fn check_anchored(anchorings: &[Anchoring]) -> Result<()> {
+    if anchorings.is_empty() {
+        bail!("document is not anchored in this wedding's history");
+    }
+    if !anchorings.iter().any(|anchoring| anchoring.by_partner) {
+        bail!("document is anchored by an outsider only, not by either partner");
+    }
+
+    Ok(())
+}
+
+pub fn print_anchorings(hash: &str, anchorings: &[Anchoring]) {
+    println!("---| document anchoring |---");
+    println!("sha256: {}", hash);
+    if anchorings.is_empty() {
+        println!("not anchored in this wedding's history");
+    }
+    for anchoring in anchorings {
+        println!(
+            "{} (slot {}){}",
+            history::format_block_time(anchoring.block_time),
+            anchoring.slot,
+            if anchoring.by_partner {
+                ""
+            } else {
+                " NOT SIGNED BY A PARTNER"
+            }
+        );
+        for signer in &anchoring.signers {
+            println!("  signed by: {:?}", signer);
+        }
+        println!(
+            "  tx: {}",
+            util::get_tx_link(&anchoring.sig, network::explorer_cluster().as_deref())
+        );
+    }
+    println!("----------------------------");
+}
@@ -0,0 +1,183 @@
+use crate::actions;
+use aes_gcm_siv::{
+    aead::{Aead, NewAead},
+    Aes256GcmSiv, Key, Nonce,
+};
+use anyhow::{anyhow, bail, Result};
+use curve25519_dalek::{edwards::CompressedEdwardsY, montgomery::MontgomeryPoint, scalar::Scalar};
+use sha2::{Digest, Sha256, Sha512};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signer::{keypair::Keypair, Signer},
+};
+
+// encrypted vows are stored as this prefix followed by base64 of
+// nonce | recipient count | wrapped content key per recipient | ciphertext
+pub const ENCRYPTED_VOWS_PREFIX: &str = "enc1:";
+
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+const TAG_LEN: usize = 16;
+const WRAPPED_KEY_LEN: usize = KEY_LEN + TAG_LEN;
+
+pub fn is_encrypted(vows: &str) -> bool {
+    vows.starts_with(ENCRYPTED_VOWS_PREFIX)
+}
+
+// the x25519 secret of an ed25519 keypair is the clamped lower half of sha512(seed), the same
+// scalar ed25519 signs with
+fn x25519_secret(keypair: &Keypair) -> Scalar {
+    let hash = Sha512::digest(&keypair.to_bytes()[..32]);
+    let mut bits = [0u8; 32];
+    bits.copy_from_slice(&hash[..32]);
+    bits[0] &= 248;
+    bits[31] &= 127;
+    bits[31] |= 64;
+
+    Scalar::from_bits(bits)
+}
+
+fn x25519_public(pubkey: &Pubkey) -> Result<MontgomeryPoint> {
+    CompressedEdwardsY(pubkey.to_bytes())
+        .decompress()
+        .map(|point| point.to_montgomery())
+        .ok_or_else(|| anyhow!("{} is not an ed25519 public key", pubkey))
+}
+
+// key wrapping the content key for one recipient, derived from the static diffie-hellman
+// secret between the author and the recipient which both sides can compute
+fn wrapping_key(shared: &MontgomeryPoint, author: &Pubkey, recipient: &Pubkey) -> Aes256GcmSiv {
+    let mut hasher = Sha256::new();
+    hasher.update(b"crypto-wedding vows");
+    hasher.update(shared.as_bytes());
+    hasher.update(author.as_ref());
+    hasher.update(recipient.as_ref());
+
+    Aes256GcmSiv::new(Key::from_slice(&hasher.finalize()))
+}
+
+// the author can always read their own vows, so they are added to the recipients
+pub fn recipients(author: &Pubkey, recipients: &[Pubkey]) -> Vec<Pubkey> {
+    let mut all = vec![*author];
+    for recipient in recipients {
+        if !all.contains(recipient) {
+            all.push(*recipient);
+        }
+    }
+
+    all
+}
+
+// length of the encoded vows, used to size transactions before encrypting
+pub fn encrypted_len(vows_len: usize, recipients: usize) -> usize {
+    let raw = NONCE_LEN + 1 + recipients * WRAPPED_KEY_LEN + vows_len + TAG_LEN;
+    ENCRYPTED_VOWS_PREFIX.len() + (raw + 2) / 3 * 4
+}
+
+pub fn encrypt_vows(author: &Keypair, recipients: &[Pubkey], vows: &str) -> Result<String> {
+    let author_pub = author.pubkey();
+    let recipients = self::recipients(&author_pub, recipients);
+    if recipients.len() > u8::MAX as usize {
+        bail!("vows can be encrypted for at most {} keys", u8::MAX);
+    }
+
+    let content_key: [u8; KEY_LEN] = rand::random();
+    let nonce: [u8; NONCE_LEN] = rand::random();
+    let nonce = Nonce::from_slice(&nonce);
+    let secret = x25519_secret(author);
+
+    let mut raw = nonce.to_vec();
+    raw.push(recipients.len() as u8);
+    for recipient in &recipients {
+        let shared = x25519_public(recipient)? * secret;
+        let wrapped = wrapping_key(&shared, &author_pub, recipient)
+            .encrypt(nonce, content_key.as_ref())
+            .map_err(|_| anyhow!("failed to wrap the vows key for {}", recipient))?;
+        raw.extend(wrapped);
+    }
+
+    let ciphertext = Aes256GcmSiv::new(Key::from_slice(&content_key))
+        .encrypt(nonce, vows.as_bytes())
+        .map_err(|_| anyhow!("failed to encrypt vows"))?;
+    raw.extend(ciphertext);
+
+    Ok(format!("{}{}", ENCRYPTED_VOWS_PREFIX, base64::encode(raw)))
+}
+
+// decrypts vows written by `author` with the reader's key, failing if the reader is not one
+// of the recipients
+pub fn decrypt_vows(reader: &Keypair, author: &Pubkey, vows: &str) -> Result<String> {
+    let encoded = vows
+        .strip_prefix(ENCRYPTED_VOWS_PREFIX)
+        .ok_or_else(|| anyhow!("vows are not encrypted"))?;
+    let raw = base64::decode(encoded)?;
+    if raw.len() < NONCE_LEN + 1 {
+        bail!("encrypted vows are truncated");
+    }
+
+    let nonce = Nonce::from_slice(&raw[..NONCE_LEN]);
+    let count = raw[NONCE_LEN] as usize;
+    let keys_end = NONCE_LEN + 1 + count * WRAPPED_KEY_LEN;
+    if raw.len() < keys_end + TAG_LEN {
+        bail!("encrypted vows are truncated");
+    }
+
+    // recipients are not stored, so every wrapped key is tried
+    let shared = x25519_public(author)? * x25519_secret(reader);
+    let unwrapping_key = wrapping_key(&shared, author, &reader.pubkey());
+    let content_key = raw[NONCE_LEN + 1..keys_end]
+        .chunks(WRAPPED_KEY_LEN)
+        .find_map(|wrapped| unwrapping_key.decrypt(nonce, wrapped).ok())
+        .ok_or_else(|| anyhow!("vows are not encrypted for {}", reader.pubkey()))?;
+
+    let plaintext = Aes256GcmSiv::new(Key::from_slice(&content_key))
+        .decrypt(nonce, &raw[keys_end..])
+        .map_err(|_| anyhow!("encrypted vows have been tampered with"))?;
+
+    Ok(String::from_utf8(plaintext)?)
+}
+
+// a missing partner account is already reported by actions::print_partner, so it is not an
+// error here
+pub fn print_decrypted_vows(reader: &Keypair, partner: &Pubkey) {
+    let state = match actions::get_partner_state(partner) {
+        Ok(state) => state,
+        Err(_) => return,
+    };
+    if !is_encrypted(&state.vows) {
+        return;
+    }
+
+    match decrypt_vows(reader, &state.user, &state.vows) {
+        Ok(vows) => println!("decrypted vows: {:?}", vows),
+        Err(err) => println!("vows are encrypted: {}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let author = Keypair::new();
+        let recipient = Keypair::new();
+        let outsider = Keypair::new();
+        let vows = "to have and to hold";
+
+        let encrypted = encrypt_vows(&author, &[recipient.pubkey()], vows).unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert_eq!(encrypted.len(), encrypted_len(vows.len(), 2));
+
+        let author_pub = author.pubkey();
+        assert_eq!(
+            decrypt_vows(&author, &author_pub, &encrypted).unwrap(),
+            vows
+        );
+        assert_eq!(
+            decrypt_vows(&recipient, &author_pub, &encrypted).unwrap(),
+            vows
+        );
+        assert!(decrypt_vows(&outsider, &author_pub, &encrypted).is_err());
+    }
+}
@@ -0,0 +1,363 @@
+use crate::{actions, amount::Amount, fee, network, util};
+use anyhow::{bail, Result};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+
+pub struct Movement {
+    pub label: String,
+    pub lamports: u64,
+    // payer for locked rent, recipient for reclaimed rent
+    pub account: Pubkey,
+}
+
+pub struct CostEstimate {
+    pub action: String,
+    // the signer paying the fees, costs and refunds are netted for this key only
+    pub payer: Pubkey,
+    pub transactions: u64,
+    pub fee_per_transaction: u64,
+    // paid when COMPUTE_UNIT_PRICE is set
+    pub priority_fee: u64,
+    pub locked: Vec<Movement>,
+    pub reclaimed: Vec<Movement>,
+}
+
+impl CostEstimate {
+    // prices the transaction made of `ixs` the way it will be sent, priority fee included
+    fn new(
+        rpc_client: &RpcClient,
+        action: &str,
+        payer: &Pubkey,
+        ixs: &[Instruction],
+    ) -> Result<CostEstimate> {
+        let mut all_ixs = fee::priority_fee_ixs()?;
+        all_ixs.extend_from_slice(ixs);
+
+        Ok(CostEstimate {
+            action: action.into(),
+            payer: payer.clone(),
+            transactions: 1,
+            fee_per_transaction: fee::base_fee(rpc_client, payer, &all_ixs)?,
+            priority_fee: fee::priority_fee(&all_ixs),
+            locked: Vec::new(),
+            reclaimed: Vec::new(),
+        })
+    }
+
+    pub fn fees(&self) -> u64 {
+        self.transactions * (self.fee_per_transaction + self.priority_fee)
+    }
+
+    fn to_payer(&self, movements: &[Movement]) -> u64 {
+        movements
+            .iter()
+            .filter(|movement| movement.account == self.payer)
+            .map(|movement| movement.lamports)
+            .sum()
+    }
+
+    // lamports leaving the payer's wallet, negative when more is reclaimed than spent. rent
+    // returned to someone else, such as the wedding creator, is not a refund for the payer.
+    pub fn net_cost(&self) -> i128 {
+        let locked = self.to_payer(&self.locked);
+        let reclaimed = self.to_payer(&self.reclaimed);
+
+        self.fees() as i128 + locked as i128 - reclaimed as i128
+    }
+
+    pub fn print(&self) {
+        println!("---| {} estimate |---", self.action);
+        println!("transactions: {}", self.transactions);
+        println!("fee per transaction: {} lamports", self.fee_per_transaction);
+        println!(
+            "priority fee per transaction: {} lamports",
+            self.priority_fee
+        );
+        for movement in &self.locked {
+            println!(
+                "locked in {}: {} paid by {:?}",
+                movement.label,
+                Amount::from_lamports(movement.lamports),
+                movement.account
+            );
+        }
+        for movement in &self.reclaimed {
+            if movement.account == self.payer {
+                println!(
+                    "reclaimed from {}: {} returned to {:?}",
+                    movement.label,
+                    Amount::from_lamports(movement.lamports),
+                    movement.account
+                );
+            }
+        }
+        for movement in &self.reclaimed {
+            if movement.account != self.payer {
+                println!(
+                    "returned to another party from {}: {} goes to {:?}",
+                    movement.label,
+                    Amount::from_lamports(movement.lamports),
+                    movement.account
+                );
+            }
+        }
+        println!("total fees: {}", Amount::from_lamports(self.fees()));
+        let net_cost = self.net_cost();
+        if net_cost < 0 {
+            println!("net refund: {}", Amount::from_lamports((-net_cost) as u64));
+        } else {
+            println!("net cost: {}", Amount::from_lamports(net_cost as u64));
+        }
+        println!("-----------------------");
+    }
+}
+
+fn wedding_movement(rpc_client: &RpcClient, creator: &Pubkey) -> Result<Movement> {
+    Ok(Movement {
+        label: "wedding".into(),
+        lamports: rpc_client.get_minimum_balance_for_rent_exemption(util::WEDDING_ACCOUNT_SIZE)?,
+        account: creator.clone(),
+    })
+}
+
+fn partner_movement(
+    rpc_client: &RpcClient,
+    user: &Pubkey,
+    name: &str,
+    vows: &str,
+) -> Result<Movement> {
+    Ok(Movement {
+        label: "partner".into(),
+        lamports: rpc_client
+            .get_minimum_balance_for_rent_exemption(util::partner_account_size(name, vows))?,
+        account: user.clone(),
+    })
+}
+
+pub fn estimate_setup_wedding(
+    creator: &Pubkey,
+    partner0: &Pubkey,
+    partner1: &Pubkey,
+) -> Result<CostEstimate> {
+    let rpc_client = RpcClient::new(network::rpc_url());
+    let mut estimate = CostEstimate::new(
+        &rpc_client,
+        "setup-wedding",
+        creator,
+        &[actions::setup_wedding_ix(creator, partner0, partner1)],
+    )?;
+    estimate
+        .locked
+        .push(wedding_movement(&rpc_client, creator)?);
+
+    Ok(estimate)
+}
+
+pub fn estimate_setup_partner(
+    user: &Pubkey,
+    other: &Pubkey,
+    name: &str,
+    vows: &str,
+) -> Result<CostEstimate> {
+    let rpc_client = RpcClient::new(network::rpc_url());
+    let mut estimate = CostEstimate::new(
+        &rpc_client,
+        "setup-partner",
+        user,
+        &[actions::setup_partner_ix(user, other, name, vows)],
+    )?;
+    estimate
+        .locked
+        .push(partner_movement(&rpc_client, user, name, vows)?);
+
+    Ok(estimate)
+}
+
+pub fn estimate_propose(
+    user: &Pubkey,
+    other: &Pubkey,
+    name: &str,
+    vows: &str,
+    answer: bool,
+) -> Result<CostEstimate> {
+    let rpc_client = RpcClient::new(network::rpc_url());
+    let mut estimate = CostEstimate::new(
+        &rpc_client,
+        "propose",
+        user,
+        &actions::propose_ixs(user, other, name, vows, answer),
+    )?;
+    estimate.locked.push(wedding_movement(&rpc_client, user)?);
+    estimate
+        .locked
+        .push(partner_movement(&rpc_client, user, name, vows)?);
+
+    Ok(estimate)
+}
+
+// updates reallocate the partner account, topping it up when the new data needs more rent.
+// `name` and `vows` are whatever is being updated, the rest keeps its current on-chain value.
+pub fn estimate_update_partner(
+    user: &Pubkey,
+    other: &Pubkey,
+    name: Option<&str>,
+    vows: Option<&str>,
+) -> Result<CostEstimate> {
+    let (action, ix) = match (name, vows) {
+        (Some(name), Some(vows)) => (
+            "update-partner",
+            actions::update_partner_ix(user, other, name, vows),
+        ),
+        (Some(name), None) => ("update-name", actions::update_name_ix(user, other, name)),
+        (None, Some(vows)) => ("update-vows", actions::update_vows_ix(user, other, vows)),
+        (None, None) => bail!("nothing to update"),
+    };
+
+    let rpc_client = RpcClient::new(network::rpc_url());
+    let mut estimate = CostEstimate::new(&rpc_client, action, user, &[ix])?;
+    let partner = actions::get_partner_state(user)?;
+    let size =
+        util::partner_account_size(name.unwrap_or(&partner.name), vows.unwrap_or(&partner.vows));
+
+    let required = rpc_client.get_minimum_balance_for_rent_exemption(size)?;
+    let current = rpc_client.get_balance(&util::find_partner_pda(user))?;
+    if required > current {
+        estimate.locked.push(Movement {
+            label: "partner".into(),
+            lamports: required - current,
+            account: user.clone(),
+        });
+    }
+
+    Ok(estimate)
+}
+
+pub fn estimate_give_answer(user: &Pubkey, other: &Pubkey, answer: bool) -> Result<CostEstimate> {
+    let rpc_client = RpcClient::new(network::rpc_url());
+
+    CostEstimate::new(
+        &rpc_client,
+        "give-answer",
+        user,
+        &[actions::give_answer_ix(user, other, answer)],
+    )
+}
+
+pub fn estimate_close_partner(user: &Pubkey, other: &Pubkey) -> Result<CostEstimate> {
+    let rpc_client = RpcClient::new(network::rpc_url());
+    let mut estimate = CostEstimate::new(
+        &rpc_client,
+        "close-partner",
+        user,
+        &[actions::close_partner_ix(user, other)],
+    )?;
+    estimate.reclaimed.push(Movement {
+        label: "partner".into(),
+        lamports: rpc_client.get_balance(&util::find_partner_pda(user))?,
+        account: user.clone(),
+    });
+
+    Ok(estimate)
+}
+
+pub fn estimate_cancel_wedding(
+    user: &Pubkey,
+    partner0: &Pubkey,
+    partner1: &Pubkey,
+) -> Result<CostEstimate> {
+    let rpc_client = RpcClient::new(network::rpc_url());
+    let wedding = actions::get_wedding_state(partner0, partner1)?;
+    let mut estimate = CostEstimate::new(
+        &rpc_client,
+        "cancel-wedding",
+        user,
+        &[actions::cancel_wedding_ix(
+            user,
+            &wedding.creator,
+            partner0,
+            partner1,
+        )],
+    )?;
+    estimate.reclaimed.push(Movement {
+        label: "wedding".into(),
+        lamports: rpc_client.get_balance(&util::find_wedding_pda(partner0, partner1))?,
+        account: wedding.creator,
+    });
+
+    Ok(estimate)
+}
+
+// divorce closes the wedding and both partner accounts, returning all of it to the wedding
+// creator, the same accounts preview::preview_divorce lists
+pub fn estimate_divorce(user: &Pubkey, other: &Pubkey) -> Result<CostEstimate> {
+    let rpc_client = RpcClient::new(network::rpc_url());
+    let wedding = actions::get_wedding_state(user, other)?;
+    let mut estimate = CostEstimate::new(
+        &rpc_client,
+        "divorce",
+        user,
+        &[actions::divorce_ix(user, &wedding.creator, other)],
+    )?;
+    for (label, account) in [
+        ("wedding", util::find_wedding_pda(user, other)),
+        ("partner", util::find_partner_pda(user)),
+        ("other partner", util::find_partner_pda(other)),
+    ] {
+        estimate.reclaimed.push(Movement {
+            label: label.into(),
+            lamports: rpc_client.get_balance(&account)?,
+            account: wedding.creator,
+        });
+    }
+
+    Ok(estimate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn movement(label: &str, lamports: u64, account: &Pubkey) -> Movement {
+        Movement {
+            label: label.into(),
+            lamports,
+            account: account.clone(),
+        }
+    }
+
+    fn estimate(payer: &Pubkey) -> CostEstimate {
+        CostEstimate {
+            action: "divorce".into(),
+            payer: payer.clone(),
+            transactions: 1,
+            fee_per_transaction: 5_000,
+            priority_fee: 100,
+            locked: Vec::new(),
+            reclaimed: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn net_cost_refunds_the_payer() {
+        let payer = Pubkey::new_unique();
+        let mut estimate = estimate(&payer);
+        estimate
+            .reclaimed
+            .push(movement("wedding", 1_000_000, &payer));
+
+        assert_eq!(estimate.net_cost(), 5_100 - 1_000_000);
+    }
+
+    #[test]
+    fn net_cost_ignores_rent_returned_to_others() {
+        let payer = Pubkey::new_unique();
+        let creator = Pubkey::new_unique();
+        let mut estimate = estimate(&payer);
+        estimate.locked.push(movement("partner", 2_000, &payer));
+        estimate
+            .reclaimed
+            .push(movement("wedding", 1_000_000, &creator));
+
+        assert_eq!(estimate.net_cost(), 7_100);
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::{Context, Result};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    compute_budget::{self, ComputeBudgetInstruction},
+    instruction::Instruction,
+    message::Message,
+    pubkey::Pubkey,
+};
+use std::env;
+
+// compute unit limit the runtime applies when a transaction does not set its own
+pub const DEFAULT_COMPUTE_UNITS_PER_INSTRUCTION: u64 = 200_000;
+pub const MAX_COMPUTE_UNITS: u64 = 1_400_000;
+const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+
+// compute budget instruction variants, borsh encodes the variant as a single byte
+const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+
+// COMPUTE_UNIT_PRICE (micro-lamports per compute unit) can be set in ~/.crypto_wedding_cli_env
+// to pay a priority fee on every transaction the cli sends, defaulting to none
+pub fn compute_unit_price() -> Result<u64> {
+    match env::var("COMPUTE_UNIT_PRICE") {
+        Ok(value) => Ok(value.trim().parse().context("invalid COMPUTE_UNIT_PRICE")?),
+        Err(_) => Ok(0),
+    }
+}
+
+// instructions to prepend to a transaction for the configured compute unit price
+pub fn priority_fee_ixs() -> Result<Vec<Instruction>> {
+    match compute_unit_price()? {
+        0 => Ok(Vec::new()),
+        price => Ok(vec![ComputeBudgetInstruction::set_compute_unit_price(
+            price,
+        )]),
+    }
+}
+
+fn is_compute_budget(ix: &Instruction) -> bool {
+    ix.program_id == compute_budget::id()
+}
+
+// priority fee paid for a transaction made of `ixs`, taken from its compute budget instructions
+pub fn priority_fee(ixs: &[Instruction]) -> u64 {
+    let mut price = 0;
+    let mut limit = None;
+    for ix in ixs.iter().filter(|ix| is_compute_budget(ix)) {
+        match ix.data.split_first() {
+            Some((&SET_COMPUTE_UNIT_PRICE, value)) if value.len() == 8 => {
+                price = u64::from_le_bytes(value.try_into().unwrap());
+            }
+            Some((&SET_COMPUTE_UNIT_LIMIT, value)) if value.len() == 4 => {
+                limit = Some(u32::from_le_bytes(value.try_into().unwrap()) as u64);
+            }
+            _ => {}
+        }
+    }
+
+    let instructions = ixs.iter().filter(|ix| !is_compute_budget(ix)).count() as u64;
+    let units = limit
+        .unwrap_or(instructions * DEFAULT_COMPUTE_UNITS_PER_INSTRUCTION)
+        .min(MAX_COMPUTE_UNITS);
+    let micro_lamports = price as u128 * units as u128;
+
+    ((micro_lamports + MICRO_LAMPORTS_PER_LAMPORT - 1) / MICRO_LAMPORTS_PER_LAMPORT) as u64
+}
+
+// signature fee for a transaction made of `ixs` paid by `payer`. the message is priced with a
+// real blockhash since the rpc rejects unknown ones. compute budget instructions are left out
+// because not every cluster version adds the priority fee here, see priority_fee instead.
+pub fn base_fee(rpc_client: &RpcClient, payer: &Pubkey, ixs: &[Instruction]) -> Result<u64> {
+    let ixs: Vec<Instruction> = ixs
+        .iter()
+        .filter(|ix| !is_compute_budget(ix))
+        .cloned()
+        .collect();
+    let mut message = Message::new(&ixs, Some(payer));
+    message.recent_blockhash = rpc_client.get_latest_blockhash()?;
+
+    Ok(rpc_client.get_fee_for_message(&message)?)
+}
+
+// total fee for a transaction made of `ixs`, priority fee included
+pub fn transaction_fee(rpc_client: &RpcClient, payer: &Pubkey, ixs: &[Instruction]) -> Result<u64> {
+    Ok(base_fee(rpc_client, payer, ixs)? + priority_fee(ixs))
+}
@@ -0,0 +1,300 @@
+use crate::{
+    actions,
+    amount::{self, Amount},
+    fee, history, network, transfer, util,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use clap::ValueEnum;
+use solana_client::rpc_client::RpcClient;
+use solana_program::program_pack::Pack;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::{keypair::Keypair, Signer},
+    system_instruction::{self, SystemInstruction},
+    system_program,
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account,
+};
+use spl_token::instruction::TokenInstruction;
+use std::{fmt, str::FromStr};
+
+pub const GIFT_MEMO_PREFIX: &str = "crypto-wedding gift ";
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum GiftRecipient {
+    Both,
+    Partner0,
+    Partner1,
+}
+
+impl GiftRecipient {
+    pub fn pubkeys(&self, partner0: &Pubkey, partner1: &Pubkey) -> Vec<Pubkey> {
+        match self {
+            GiftRecipient::Both => vec![*partner0, *partner1],
+            GiftRecipient::Partner0 => vec![*partner0],
+            GiftRecipient::Partner1 => vec![*partner1],
+        }
+    }
+}
+
+// lamports when `mint` is none, otherwise base units of the token with its decimals
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GiftAmount {
+    pub units: u64,
+    pub mint: Option<(Pubkey, u8)>,
+}
+
+impl fmt::Display for GiftAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.mint {
+            None => write!(f, "{}", Amount::from_lamports(self.units)),
+            Some((mint, decimals)) => write!(
+                f,
+                "{} of {}",
+                amount::format_decimal(self.units, decimals),
+                mint
+            ),
+        }
+    }
+}
+
+pub fn mint_decimals(rpc_client: &RpcClient, mint: &Pubkey) -> Result<u8> {
+    let account = rpc_client.get_account(mint)?;
+    if account.owner != spl_token::id() {
+        bail!("{} is not an spl token mint", mint);
+    }
+
+    Ok(spl_token::state::Mint::unpack(&account.data)?.decimals)
+}
+
+// SOL amounts accept the same forms as elsewhere, token amounts are decimals of the mint
+pub fn parse_gift_amount(
+    rpc_client: &RpcClient,
+    amount: &str,
+    mint: Option<&Pubkey>,
+) -> Result<GiftAmount> {
+    match mint {
+        None => Ok(GiftAmount {
+            units: Amount::from_str(amount)?.lamports(),
+            mint: None,
+        }),
+        Some(mint) => {
+            let decimals = mint_decimals(rpc_client, mint)?;
+            let units = amount::parse_decimal(amount.trim(), decimals).ok_or_else(|| {
+                anyhow!(
+                    "invalid amount {:?}, expected a decimal with at most {} fraction digits",
+                    amount,
+                    decimals
+                )
+            })?;
+
+            Ok(GiftAmount {
+                units,
+                mint: Some((*mint, decimals)),
+            })
+        }
+    }
+}
+
+pub fn gift_memo(wedding: &Pubkey, message: &str) -> String {
+    format!("{}{}: {}", GIFT_MEMO_PREFIX, wedding, message)
+}
+
+// sends `amount` to every recipient in one transaction, creating their associated token
+// accounts when needed. the zero lamport transfer to the wedding puts the gift in its history,
+// so the wedding has to exist.
+pub fn send_gift(
+    signer: &Keypair,
+    partner0: &Pubkey,
+    partner1: &Pubkey,
+    recipients: &[Pubkey],
+    amount: GiftAmount,
+    message: &str,
+) -> Result<Signature> {
+    let rpc_client = RpcClient::new(network::rpc_url());
+    let signer_pub = signer.pubkey();
+    let wedding = util::find_wedding_pda(partner0, partner1);
+    actions::get_wedding_state(partner0, partner1)
+        .with_context(|| format!("wedding {} does not exist", wedding))?;
+
+    let mut ixs = fee::priority_fee_ixs()?;
+    ixs.push(system_instruction::transfer(&signer_pub, &wedding, 0));
+    for recipient in recipients {
+        match amount.mint {
+            None => ixs.push(system_instruction::transfer(
+                &signer_pub,
+                recipient,
+                amount.units,
+            )),
+            Some((mint, decimals)) => {
+                let destination = get_associated_token_address(recipient, &mint);
+                if rpc_client
+                    .get_account_with_commitment(&destination, rpc_client.commitment())?
+                    .value
+                    .is_none()
+                {
+                    println!(
+                        "creating token account {:?} for {:?}",
+                        destination, recipient
+                    );
+                    ixs.push(create_associated_token_account(
+                        &signer_pub,
+                        recipient,
+                        &mint,
+                    ));
+                }
+                ixs.push(spl_token::instruction::transfer_checked(
+                    &spl_token::id(),
+                    &get_associated_token_address(&signer_pub, &mint),
+                    &mint,
+                    &destination,
+                    &signer_pub,
+                    &[],
+                    amount.units,
+                    decimals,
+                )?);
+            }
+        }
+    }
+    ixs.push(spl_memo::build_memo(
+        gift_memo(&wedding, message).as_bytes(),
+        &[&signer_pub],
+    ));
+
+    let bytes_left = util::tx_bytes_left(&signer_pub, &ixs);
+    if bytes_left < 0 {
+        bail!(
+            "gift message is {} bytes too long for a single transaction",
+            -bytes_left
+        );
+    }
+
+    transfer::send_transaction(&rpc_client, signer, &ixs)
+}
+
+pub struct ReceivedGift {
+    pub sig: Signature,
+    pub block_time: Option<i64>,
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: GiftAmount,
+    pub message: String,
+}
+
+// the partner receiving a transfer, if any, from a system or checked token transfer
+fn gift_transfer(
+    program_id: &Pubkey,
+    accounts: &[Pubkey],
+    data: &[u8],
+    partners: [&Pubkey; 2],
+) -> Option<(Pubkey, GiftAmount)> {
+    if program_id == &system_program::id() {
+        match bincode::deserialize::<SystemInstruction>(data).ok()? {
+            SystemInstruction::Transfer { lamports } if lamports > 0 => {
+                let to = accounts.get(1)?;
+                partners.contains(&to).then(|| {
+                    (
+                        *to,
+                        GiftAmount {
+                            units: lamports,
+                            mint: None,
+                        },
+                    )
+                })
+            }
+            _ => None,
+        }
+    } else if program_id == &spl_token::id() {
+        match TokenInstruction::unpack(data).ok()? {
+            TokenInstruction::TransferChecked { amount, decimals } => {
+                let (mint, destination) = (accounts.get(1)?, accounts.get(2)?);
+                let recipient = partners
+                    .iter()
+                    .find(|partner| &get_associated_token_address(partner, mint) == destination)?;
+
+                Some((
+                    **recipient,
+                    GiftAmount {
+                        units: amount,
+                        mint: Some((*mint, decimals)),
+                    },
+                ))
+            }
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
+// gifts received by either partner, reconstructed from the gift memos in the wedding history
+pub fn list_gifts(partner0: &Pubkey, partner1: &Pubkey) -> Result<Vec<ReceivedGift>> {
+    let prefix = gift_memo(&util::find_wedding_pda(partner0, partner1), "");
+    let mut gifts = Vec::new();
+
+    for fetched in history::get_history(partner0, partner1)? {
+        if fetched.failed() {
+            continue;
+        }
+        let message = match fetched
+            .memos()
+            .iter()
+            .find_map(|memo| memo.strip_prefix(&prefix))
+        {
+            Some(message) => message.to_string(),
+            None => continue,
+        };
+
+        let keys = &fetched.transaction.message.account_keys;
+        for ix in &fetched.transaction.message.instructions {
+            let accounts: Vec<Pubkey> = ix
+                .accounts
+                .iter()
+                .map(|index| keys[*index as usize])
+                .collect();
+            if let Some((recipient, amount)) = gift_transfer(
+                &keys[ix.program_id_index as usize],
+                &accounts,
+                &ix.data,
+                [partner0, partner1],
+            ) {
+                gifts.push(ReceivedGift {
+                    sig: fetched.sig,
+                    block_time: fetched.block_time,
+                    sender: fetched.signers()[0],
+                    recipient,
+                    amount,
+                    message: message.clone(),
+                });
+            }
+        }
+    }
+
+    Ok(gifts)
+}
+
+pub fn print_gifts(partner0: &Pubkey, partner1: &Pubkey, gifts: &[ReceivedGift]) {
+    println!("---| wedding gifts |---");
+    println!("wedding: {:?}", util::find_wedding_pda(partner0, partner1));
+    if gifts.is_empty() {
+        println!("no gifts found");
+    }
+    for gift in gifts {
+        println!();
+        println!(
+            "{}: {} to {:?}",
+            history::format_block_time(gift.block_time),
+            gift.amount,
+            gift.recipient
+        );
+        println!("  from: {:?}", gift.sender);
+        println!("  message: {:?}", gift.message);
+        println!(
+            "  tx: {}",
+            util::get_tx_link(&gift.sig, network::explorer_cluster().as_deref())
+        );
+    }
+    println!("-----------------------");
+}
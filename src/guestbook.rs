@@ -0,0 +1,148 @@
+use crate::{
+    message::{self, SignedMessage},
+    util,
+};
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signer::{keypair::Keypair, Signer},
+};
+use std::{fs, path::PathBuf};
+
+pub const GUESTBOOK_ENTRY_HEADER: &str = "crypto wedding guestbook entry";
+
+pub struct GuestbookEntry {
+    pub guest: Pubkey,
+    pub name: Option<String>,
+    pub message: String,
+}
+
+impl GuestbookEntry {
+    // the guest's name when given, otherwise their key
+    pub fn author(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{} ({})", name, self.guest),
+            None => self.guest.to_string(),
+        }
+    }
+}
+
+pub fn sign_entry(
+    guest: &Keypair,
+    wedding: &Pubkey,
+    name: Option<&str>,
+    text: &str,
+) -> SignedMessage {
+    let mut headers = vec![
+        ("program", util::program_id().to_string()),
+        ("guest", guest.pubkey().to_string()),
+        ("wedding", wedding.to_string()),
+    ];
+    if let Some(name) = name {
+        headers.push(("name", name.replace('\n', " ")));
+    }
+
+    SignedMessage::sign(
+        guest,
+        message::build_payload(GUESTBOOK_ENTRY_HEADER, &headers, text),
+    )
+}
+
+// checks the entry is signed by the guest it names and is bound to `wedding`
+pub fn verify_entry(entry: &SignedMessage, wedding: &Pubkey) -> Result<GuestbookEntry> {
+    if entry.kind() != GUESTBOOK_ENTRY_HEADER {
+        bail!("not a guestbook entry: {}", entry.kind());
+    }
+    entry.verify()?;
+
+    if entry.header_pubkey("guest")? != entry.signer {
+        bail!("guest header does not match the signing key");
+    }
+    if &entry.header_pubkey("wedding")? != wedding {
+        bail!("entry was signed for a different wedding");
+    }
+    if entry.header_pubkey("program")? != util::program_id() {
+        bail!("entry was signed for a different program");
+    }
+
+    Ok(GuestbookEntry {
+        guest: entry.signer,
+        name: entry.headers().get("name").cloned(),
+        message: entry.body().to_string(),
+    })
+}
+
+// a file holds either a single signed entry or a guest book array of them
+pub fn load(path: &PathBuf) -> Result<Vec<SignedMessage>> {
+    let text =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let json: Value =
+        serde_json::from_str(&text).with_context(|| format!("{} is not json", path.display()))?;
+
+    match json {
+        Value::Array(entries) => entries.iter().map(SignedMessage::from_json).collect(),
+        entry => Ok(vec![SignedMessage::from_json(&entry)?]),
+    }
+}
+
+pub fn save(path: &PathBuf, entries: &[SignedMessage]) -> Result<()> {
+    let json = Value::Array(entries.iter().map(SignedMessage::to_json).collect());
+
+    fs::write(path, serde_json::to_string_pretty(&json)?)
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+// merges the entries of every file, dropping the ones which fail verification and duplicates.
+// entries are ordered by guest so collecting the same files always gives the same guest book.
+pub fn collect(paths: &[PathBuf], wedding: &Pubkey) -> Result<Vec<SignedMessage>> {
+    let mut entries: Vec<SignedMessage> = Vec::new();
+    for path in paths {
+        for entry in load(path)? {
+            if let Err(err) = verify_entry(&entry, wedding) {
+                println!("skipping entry in {}: {}", path.display(), err);
+                continue;
+            }
+            if entries
+                .iter()
+                .any(|collected| collected.signature == entry.signature)
+            {
+                continue;
+            }
+            entries.push(entry);
+        }
+    }
+
+    entries.sort_by(|a, b| (a.signer, &a.payload).cmp(&(b.signer, &b.payload)));
+
+    Ok(entries)
+}
+
+// verifies every entry of a guest book, failing on the first invalid one
+pub fn verify_guestbook(
+    entries: &[SignedMessage],
+    wedding: &Pubkey,
+) -> Result<Vec<GuestbookEntry>> {
+    entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            verify_entry(entry, wedding)
+                .with_context(|| format!("guestbook entry {} is invalid", i))
+        })
+        .collect()
+}
+
+pub fn print_guestbook(wedding: &Pubkey, entries: &[GuestbookEntry]) {
+    println!("---| guest book |---");
+    println!("wedding: {:?}", wedding);
+    println!("entries: {}", entries.len());
+    for entry in entries {
+        println!();
+        println!("{}", entry.author());
+        for line in entry.message.lines() {
+            println!("  {}", line);
+        }
+    }
+    println!("--------------------");
+}
@@ -0,0 +1,180 @@
+use crate::{decode::WeddingInstruction, network, util};
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDateTime;
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_sdk::{pubkey::Pubkey, signature::Signature, transaction::Transaction};
+use solana_transaction_status::{
+    EncodedTransaction, UiTransactionEncoding, UiTransactionStatusMeta,
+};
+use std::{collections::HashSet, str::FromStr};
+
+pub struct FetchedTransaction {
+    pub sig: Signature,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub transaction: Transaction,
+    pub meta: Option<UiTransactionStatusMeta>,
+}
+
+impl FetchedTransaction {
+    pub fn signers(&self) -> &[Pubkey] {
+        let num_signers = self.transaction.message.header.num_required_signatures as usize;
+        &self.transaction.message.account_keys[..num_signers]
+    }
+
+    pub fn touches(&self, account: &Pubkey) -> bool {
+        self.transaction.message.account_keys.contains(account)
+    }
+
+    // crypto wedding instructions for `wedding` in the order they appear in the transaction.
+    // instructions for other weddings sharing the transaction are left out.
+    pub fn wedding_instructions(&self, wedding: &Pubkey) -> Vec<WeddingInstruction> {
+        let message = &self.transaction.message;
+        let program_id = util::program_id();
+        message
+            .instructions
+            .iter()
+            .filter(|ix| message.account_keys[ix.program_id_index as usize] == program_id)
+            .filter(|ix| {
+                ix.accounts
+                    .iter()
+                    .any(|index| message.account_keys.get(*index as usize) == Some(wedding))
+            })
+            .map(|ix| {
+                WeddingInstruction::decode(&ix.data).unwrap_or(WeddingInstruction::Unknown {
+                    discriminator: ix.data.iter().take(8).cloned().collect(),
+                })
+            })
+            .collect()
+    }
+
+    // utf-8 memos attached with the spl memo program
+    pub fn memos(&self) -> Vec<String> {
+        let message = &self.transaction.message;
+        message
+            .instructions
+            .iter()
+            .filter(|ix| message.account_keys[ix.program_id_index as usize] == spl_memo::id())
+            .filter_map(|ix| String::from_utf8(ix.data.clone()).ok())
+            .collect()
+    }
+
+    pub fn failed(&self) -> bool {
+        matches!(&self.meta, Some(meta) if meta.err.is_some())
+    }
+}
+
+pub fn format_block_time(block_time: Option<i64>) -> String {
+    match block_time.and_then(|time| NaiveDateTime::from_timestamp_opt(time, 0)) {
+        Some(date) => format!("{} UTC", date.format("%Y-%m-%d %H:%M:%S")),
+        None => "unknown time".into(),
+    }
+}
+
+// every signature which touched `address`, newest first, paging back until the start of history
+pub fn get_signatures(rpc_client: &RpcClient, address: &Pubkey) -> Result<Vec<Signature>> {
+    let mut sigs = Vec::new();
+    let mut before = None;
+
+    loop {
+        let page = rpc_client.get_signatures_for_address_with_config(
+            address,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: None,
+                limit: None,
+                commitment: None,
+            },
+        )?;
+
+        if page.is_empty() {
+            break;
+        }
+
+        for status in &page {
+            sigs.push(Signature::from_str(&status.signature)?);
+        }
+        before = sigs.last().cloned();
+    }
+
+    Ok(sigs)
+}
+
+pub fn get_transaction(rpc_client: &RpcClient, sig: &Signature) -> Result<FetchedTransaction> {
+    let fetched = rpc_client.get_transaction(sig, UiTransactionEncoding::Base64)?;
+
+    let transaction = match &fetched.transaction.transaction {
+        EncodedTransaction::Binary(blob, _) => {
+            let bytes = base64::decode(blob).context("transaction is not valid base64")?;
+            bincode::deserialize::<Transaction>(&bytes)
+                .context("only legacy transactions can be decoded")?
+        }
+        _ => bail!("unexpected transaction encoding for {}", sig),
+    };
+
+    Ok(FetchedTransaction {
+        sig: sig.clone(),
+        slot: fetched.slot,
+        block_time: fetched.block_time,
+        transaction,
+        meta: fetched.transaction.meta,
+    })
+}
+
+// all transactions which touched the wedding account, oldest first. every crypto wedding
+// instruction lists the wedding, and memos are sent with a transfer to it, so partner accounts
+// are not walked: they outlive a wedding and would pull in transactions for other weddings.
+// transactions which can't be fetched or decoded are reported and skipped.
+pub fn get_history(partner0: &Pubkey, partner1: &Pubkey) -> Result<Vec<FetchedTransaction>> {
+    let rpc_client = RpcClient::new(network::rpc_url());
+    let wedding = util::find_wedding_pda(partner0, partner1);
+
+    let mut seen = HashSet::new();
+    let mut history = Vec::new();
+    for sig in get_signatures(&rpc_client, &wedding)? {
+        if !seen.insert(sig) {
+            continue;
+        }
+
+        match get_transaction(&rpc_client, &sig) {
+            Ok(fetched) if fetched.touches(&wedding) => history.push(fetched),
+            Ok(_) => {}
+            Err(err) => println!("skipping transaction {}: {:#}", sig, err),
+        }
+    }
+
+    history.sort_by_key(|fetched| (fetched.slot, fetched.block_time));
+
+    Ok(history)
+}
+
+pub fn print_history(partner0: &Pubkey, partner1: &Pubkey, history: &[FetchedTransaction]) {
+    let wedding = util::find_wedding_pda(partner0, partner1);
+
+    println!("---| wedding history |---");
+    println!("wedding: {:?}", wedding);
+    for fetched in history {
+        let instructions = fetched.wedding_instructions(&wedding);
+        if instructions.is_empty() {
+            continue;
+        }
+
+        println!(
+            "{} (slot {}){}",
+            format_block_time(fetched.block_time),
+            fetched.slot,
+            if fetched.failed() { " FAILED" } else { "" }
+        );
+        for instruction in instructions {
+            println!("  {}", instruction);
+        }
+        for signer in fetched.signers() {
+            println!("  signed by: {:?}", signer);
+        }
+        println!(
+            "  tx: {}",
+            util::get_tx_link(&fetched.sig, network::explorer_cluster().as_deref())
+        );
+    }
+    println!("-------------------------");
+}
@@ -0,0 +1,189 @@
+use anyhow::{anyhow, bail, Context, Result};
+use clap::ValueEnum;
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    path::PathBuf,
+    process::Command,
+};
+
+fn trim_trailing_newline(mut text: String) -> String {
+    if text.ends_with('\n') {
+        text.pop();
+        if text.ends_with('\r') {
+            text.pop();
+        }
+    }
+
+    text
+}
+
+pub fn read_stdin() -> Result<String> {
+    let mut text = String::new();
+    io::stdin()
+        .read_to_string(&mut text)
+        .context("failed to read from stdin")?;
+
+    Ok(trim_trailing_newline(text))
+}
+
+pub fn read_file(path: &PathBuf) -> Result<String> {
+    let text =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+
+    Ok(trim_trailing_newline(text))
+}
+
+// the file is created exclusively with a random name and owner-only permissions, so another
+// user can neither read the vows nor plant a file at the path before the editor opens it
+fn create_temp_file() -> Result<(PathBuf, File)> {
+    for _ in 0..16 {
+        let path = env::temp_dir().join(format!(
+            "crypto_wedding_vows_{:016x}.txt",
+            rand::random::<u64>()
+        ));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to create {}", path.display()))
+            }
+        }
+    }
+
+    bail!("failed to create a temporary vows file")
+}
+
+// opens $EDITOR (falling back to vi) on a temporary file prefilled with `initial`
+pub fn read_editor(initial: &str) -> Result<String> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".into());
+    let (path, mut file) = create_temp_file()?;
+    let written = file.write_all(initial.as_bytes());
+    drop(file);
+    if let Err(err) = written {
+        fs::remove_file(&path).ok();
+        return Err(err).context("failed to write temporary vows file");
+    }
+
+    let status = Command::new(&editor)
+        .arg(&path)
+        .status()
+        .with_context(|| format!("failed to launch editor {}", editor))?;
+    let text = fs::read_to_string(&path);
+    fs::remove_file(&path).ok();
+
+    if !status.success() {
+        bail!("editor {} exited with {}", editor, status);
+    }
+
+    Ok(trim_trailing_newline(text?))
+}
+
+// resolves vows from exactly one of --vows (where "-" means stdin), --vows-file or --edit.
+// `current` is used to prefill the editor, usually with the on-chain vows.
+pub fn read_vows(
+    vows: Option<String>,
+    vows_file: Option<PathBuf>,
+    edit: bool,
+    current: Option<String>,
+) -> Result<String> {
+    match (vows, vows_file, edit) {
+        (Some(vows), None, false) if vows == "-" => read_stdin(),
+        (Some(vows), None, false) => Ok(vows),
+        (None, Some(path), false) => read_file(&path),
+        (None, None, true) => read_editor(current.unwrap_or_default().as_ref()),
+        (None, None, false) => bail!("one of --vows, --vows-file or --edit is required"),
+        _ => bail!("only one of --vows, --vows-file or --edit can be used"),
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Encoding {
+    Base64,
+    Base58,
+    Hex,
+    // output of `solana account --output json`
+    Json,
+}
+
+fn decode_hex(text: &str) -> Result<Vec<u8>> {
+    let text = text.trim_start_matches("0x");
+    // checked up front so slicing below always lands on char boundaries
+    if let Some(position) = text.bytes().position(|b| !b.is_ascii_hexdigit()) {
+        bail!("invalid hex digit at position {}", position);
+    }
+    if text.len() % 2 != 0 {
+        bail!("hex data has an odd number of digits");
+    }
+
+    (0..text.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&text[i..i + 2], 16)
+                .map_err(|_| anyhow!("invalid hex digits at position {}", i))
+        })
+        .collect()
+}
+
+fn decode_account_json(text: &str) -> Result<Vec<u8>> {
+    let json: serde_json::Value = serde_json::from_str(text).context("invalid account json")?;
+    let data = json
+        .get("account")
+        .unwrap_or(&json)
+        .get("data")
+        .ok_or_else(|| anyhow!("account json has no data field"))?;
+
+    // data is encoded as [data, encoding]
+    match (
+        data.get(0).and_then(|d| d.as_str()),
+        data.get(1).and_then(|e| e.as_str()),
+    ) {
+        (Some(data), Some("base64")) => Ok(base64::decode(data)?),
+        (Some(data), Some("base58")) => Ok(bs58::decode(data).into_vec()?),
+        (_, Some(encoding)) => bail!("unsupported account data encoding {}", encoding),
+        _ => bail!("account data should be a [data, encoding] pair"),
+    }
+}
+
+pub fn decode_data(text: &str, encoding: Encoding) -> Result<Vec<u8>> {
+    let text = text.trim();
+    // json dumps are recognised regardless of the requested encoding
+    if text.starts_with('{') {
+        return decode_account_json(text);
+    }
+
+    match encoding {
+        Encoding::Base64 => Ok(base64::decode(text).context("invalid base64 data")?),
+        Encoding::Base58 => Ok(bs58::decode(text)
+            .into_vec()
+            .context("invalid base58 data")?),
+        Encoding::Hex => decode_hex(text),
+        Encoding::Json => decode_account_json(text),
+    }
+}
+
+// reads encoded data from `data`, a file, or stdin when neither is given or `data` is "-"
+pub fn read_data(
+    data: Option<String>,
+    file: Option<PathBuf>,
+    encoding: Encoding,
+) -> Result<Vec<u8>> {
+    let text = match (data, file) {
+        (Some(data), None) if data != "-" => data,
+        (None, Some(path)) => read_file(&path)?,
+        (Some(_), None) | (None, None) => read_stdin()?,
+        (Some(_), Some(_)) => bail!("only one of data or --file can be used"),
+    };
+
+    decode_data(&text, encoding)
+}
@@ -0,0 +1,206 @@
+use crate::{
+    decode::WeddingInstruction,
+    history::{self, FetchedTransaction},
+    network, util,
+};
+use anyhow::{anyhow, Context, Result};
+use flate2::read::ZlibDecoder;
+use serde_json::Value;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::InstructionError, message::Message, pubkey::Pubkey, transaction::TransactionError,
+};
+use std::io::Read;
+
+// anchor reserves error numbers below 6000 for the framework, program errors start at 6000
+pub const ANCHOR_ERROR_OFFSET: u32 = 6000;
+
+// an error declared by the program's ErrorCode enum
+pub struct IdlError {
+    pub code: u32,
+    pub name: String,
+    pub msg: Option<String>,
+}
+
+// anchor publishes the idl at an address derived from the program id
+pub fn idl_address(program_id: &Pubkey) -> Result<Pubkey> {
+    let (base, _) = Pubkey::find_program_address(&[], program_id);
+
+    Ok(Pubkey::create_with_seed(&base, "anchor:idl", program_id)?)
+}
+
+// reads the error codes from the idl the program published on chain. the idl account holds a
+// discriminator, the authority and the length prefixed zlib compressed idl json.
+pub fn fetch_idl_errors(program_id: &Pubkey) -> Result<Vec<IdlError>> {
+    let rpc_client = RpcClient::new(network::rpc_url());
+    let data = rpc_client.get_account_data(&idl_address(program_id)?)?;
+
+    let len = data
+        .get(40..44)
+        .ok_or_else(|| anyhow!("idl account is too short"))?;
+    let len = u32::from_le_bytes(len.try_into()?) as usize;
+    let compressed = data
+        .get(44..44 + len)
+        .ok_or_else(|| anyhow!("idl account is too short"))?;
+
+    let mut json = String::new();
+    ZlibDecoder::new(compressed)
+        .read_to_string(&mut json)
+        .context("failed to decompress idl")?;
+    let idl: Value = serde_json::from_str(&json).context("invalid idl json")?;
+
+    Ok(idl
+        .get("errors")
+        .and_then(Value::as_array)
+        .map(|errors| {
+            errors
+                .iter()
+                .filter_map(|error| {
+                    Some(IdlError {
+                        code: error.get("code")?.as_u64()? as u32,
+                        name: error.get("name")?.as_str()?.to_string(),
+                        msg: error.get("msg").and_then(Value::as_str).map(String::from),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+fn compute_units(logs: &[String]) -> Vec<(String, String)> {
+    logs.iter()
+        .filter_map(|log| {
+            let rest = log.strip_prefix("Program ")?;
+            let (program, rest) = rest.split_once(" consumed ")?;
+            Some((program.to_string(), rest.to_string()))
+        })
+        .collect()
+}
+
+// the AnchorError log line is preferred, `errors` from the idl name the error when logs are
+// missing or truncated
+pub fn describe_error(err: &TransactionError, logs: &[String], errors: &[IdlError]) -> String {
+    let anchor_log = logs
+        .iter()
+        .find(|log| log.starts_with("Program log: AnchorError"))
+        .map(|log| log.trim_start_matches("Program log: ").to_string());
+
+    match err {
+        TransactionError::InstructionError(index, InstructionError::Custom(code)) => {
+            let kind = if *code >= ANCHOR_ERROR_OFFSET {
+                format!("program error #{}", code - ANCHOR_ERROR_OFFSET)
+            } else {
+                "anchor framework error".into()
+            };
+
+            let idl_error = errors.iter().find(|error| error.code == *code);
+
+            match (anchor_log, idl_error) {
+                (Some(anchor_log), _) => format!(
+                    "instruction {} failed with custom error {} ({}): {}",
+                    index, code, kind, anchor_log
+                ),
+                (None, Some(IdlError { name, msg, .. })) => format!(
+                    "instruction {} failed with custom error {} ({}): {}{}",
+                    index,
+                    code,
+                    kind,
+                    name,
+                    msg.as_ref()
+                        .map(|msg| format!(", {}", msg))
+                        .unwrap_or_default()
+                ),
+                (None, None) => format!(
+                    "instruction {} failed with custom error {} ({})",
+                    index, code, kind
+                ),
+            }
+        }
+        _ => format!("{:?}", err),
+    }
+}
+
+// program of the instruction a transaction error points at
+fn failed_program(message: &Message, err: &TransactionError) -> Option<Pubkey> {
+    match err {
+        TransactionError::InstructionError(index, _) => message
+            .instructions
+            .get(*index as usize)
+            .map(|ix| message.account_keys[ix.program_id_index as usize]),
+        _ => None,
+    }
+}
+
+pub fn print_transaction(fetched: &FetchedTransaction) {
+    let message = &fetched.transaction.message;
+    let wedding_program_id = util::program_id();
+
+    println!("---| transaction |---");
+    println!("signature: {}", fetched.sig);
+    println!("slot: {}", fetched.slot);
+    println!("time: {}", history::format_block_time(fetched.block_time));
+    for signer in fetched.signers() {
+        println!("signed by: {:?}", signer);
+    }
+
+    for (i, ix) in message.instructions.iter().enumerate() {
+        let program_id = message.account_keys[ix.program_id_index as usize];
+        if program_id != wedding_program_id {
+            println!("instruction {}: program {:?}", i, program_id);
+            continue;
+        }
+
+        match WeddingInstruction::decode(&ix.data) {
+            Ok(instruction) => {
+                println!("instruction {}: crypto wedding {}", i, instruction);
+                let names = instruction.account_names();
+                for (position, account_index) in ix.accounts.iter().enumerate() {
+                    println!(
+                        "  {}: {:?}",
+                        names.get(position).unwrap_or(&"unknown"),
+                        message.account_keys[*account_index as usize]
+                    );
+                }
+            }
+            Err(err) => {
+                println!(
+                    "instruction {}: crypto wedding, failed to decode args: {:?}",
+                    i, err
+                );
+            }
+        }
+    }
+
+    match &fetched.meta {
+        Some(meta) => {
+            let logs = meta.log_messages.clone().unwrap_or_default();
+            println!("fee: {} lamports", meta.fee);
+            for (program, consumed) in compute_units(&logs) {
+                println!("compute units: {} consumed {}", program, consumed);
+            }
+            match &meta.err {
+                Some(err) => {
+                    // the idl only names errors raised by the wedding program
+                    let errors = if failed_program(message, err) == Some(wedding_program_id) {
+                        fetch_idl_errors(&wedding_program_id).unwrap_or_default()
+                    } else {
+                        Vec::new()
+                    };
+                    println!("status: failed, {}", describe_error(err, &logs, &errors));
+                }
+                None => println!("status: success"),
+            }
+            println!("logs:");
+            for log in &logs {
+                println!("  {}", log);
+            }
+        }
+        None => println!("status: unknown, no transaction meta returned"),
+    }
+
+    println!(
+        "tx: {}",
+        util::get_tx_link(&fetched.sig, network::explorer_cluster().as_deref())
+    );
+    println!("---------------------");
+}
@@ -1,3 +1,26 @@
 pub mod actions;
+pub mod amount;
+pub mod ceremony;
+pub mod certificate;
+pub mod commitment;
+pub mod decode;
+pub mod derive;
+pub mod document;
+pub mod encryption;
+pub mod estimate;
+pub mod fee;
+pub mod gift;
+pub mod guestbook;
+pub mod history;
+pub mod input;
+pub mod inspect;
+pub mod message;
 pub mod network;
+pub mod policy;
+pub mod preview;
+pub mod proof;
+pub mod qr;
+pub mod teardown;
+pub mod transfer;
 pub mod util;
+pub mod witness;
@@ -1,27 +1,43 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use clap::{Args, Parser, Subcommand};
-use crypto_wedding_cli::{actions, network, util};
+use crypto_wedding_cli::{
+    actions, amount::Amount, ceremony, certificate, commitment, decode, derive, document,
+    encryption, estimate, fee, gift, guestbook, history, input, inspect, message, network,
+    policy::Policy, preview, proof, qr, teardown, transfer, util, witness,
+};
 use dotenv;
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::{
-    commitment_config::CommitmentConfig,
+    instruction::Instruction,
     pubkey::Pubkey,
+    signature::Signature,
     signer::{keypair::Keypair, Signer},
 };
-use std::env;
+use std::{env, path::PathBuf};
 
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)] // Reads these fields from `Cargo.toml`
 struct Cli {
     #[clap(subcommand)]
     command: Commands,
+    #[clap(value_parser, long, global = true)]
+    i_know_this_is_mainnet: bool,
+    #[clap(value_parser, long, global = true)]
+    program_id: Option<Pubkey>,
+    // load ~/.crypto_wedding_cli_env.<PROFILE> on top of the default env file
+    #[clap(value_parser, long, global = true)]
+    profile: Option<String>,
+    // print the cost of the command instead of running it
+    #[clap(value_parser, long, global = true)]
+    estimate: bool,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     GetOwnAccount,
-    AirdropFunds,
-    CreateAndAirdropAccount, // mostly used for testing...
+    AirdropFunds(AirdropFunds),
+    CreateAndAirdropAccount(CreateAndAirdropAccount), // mostly used for testing...
+    EnsureFunded(EnsureFunded),
     SetupWedding(SetupWedding),
     CancelWedding(CancelWedding),
     SetupPartner(SetupPartner),
@@ -30,10 +46,125 @@ enum Commands {
     UpdateName(UpdateName),
     UpdateVows(UpdateVows),
     GiveAnswer(GiveAnswer),
+    Propose(Propose),
     Divorce(Divorce),
+    Ceremony(Ceremony),
     PrintWedding(PrintWedding),
     PrintPartner(PrintPartner),
     WatchWedding(WatchWedding),
+    History(History),
+    InspectTx(InspectTx),
+    DecodeAccount(DecodeAccount),
+    Derive(Derive),
+    Estimate(Estimate),
+    Balances(Balances),
+    Fund(Fund),
+    FundCouple(FundCouple),
+    Teardown(Teardown),
+    Certificate(Certificate),
+    Qr(Qr),
+    ProveMarriage(ProveMarriage),
+    VerifyProof(VerifyProof),
+    SignMessage(SignMessage),
+    VerifyMessage(VerifyMessage),
+    CommitVows(CommitVows),
+    RevealVows(RevealVows),
+    VerifyVows(VerifyVows),
+    AnchorDocument(AnchorDocument),
+    VerifyDocument(VerifyDocument),
+    Guestbook(Guestbook),
+    Witness(Witness),
+    Gift(Gift),
+    Gifts(Gifts),
+}
+
+impl Commands {
+    // offline commands run without SIGNER_PRIV or any network access
+    fn is_offline(&self) -> bool {
+        match self {
+            Commands::DecodeAccount(_) | Commands::Derive(_) => true,
+            Commands::VerifyProof(VerifyProof { check_chain, .. }) => !check_chain,
+            Commands::VerifyMessage(VerifyMessage { check_chain, .. }) => !check_chain,
+            // guests sign with SIGNER_PRIV, everything else works on files
+            Commands::Guestbook(Guestbook { command }) => {
+                !matches!(command, GuestbookCommands::Sign(_))
+            }
+            _ => false,
+        }
+    }
+
+    fn is_airdrop(&self) -> bool {
+        matches!(
+            self,
+            Commands::AirdropFunds(_)
+                | Commands::CreateAndAirdropAccount(_)
+                | Commands::EnsureFunded(_)
+        )
+    }
+
+    fn is_mutating(&self) -> bool {
+        !matches!(
+            self,
+            Commands::GetOwnAccount
+                | Commands::AirdropFunds(_)
+                | Commands::CreateAndAirdropAccount(_)
+                | Commands::EnsureFunded(_)
+                | Commands::PrintWedding(_)
+                | Commands::PrintPartner(_)
+                | Commands::WatchWedding(_)
+                | Commands::History(_)
+                | Commands::InspectTx(_)
+                | Commands::DecodeAccount(_)
+                | Commands::Derive(_)
+                | Commands::Estimate(_)
+                | Commands::Balances(_)
+                | Commands::Certificate(_)
+                | Commands::Qr(_)
+                | Commands::ProveMarriage(_)
+                | Commands::VerifyProof(_)
+                | Commands::SignMessage(_)
+                | Commands::VerifyMessage(_)
+                | Commands::VerifyVows(_)
+                | Commands::VerifyDocument(_)
+                | Commands::Guestbook(_)
+                | Commands::Witness(Witness {
+                    command: WitnessCommands::Attest(WitnessAttest { submit: false, .. })
+                        | WitnessCommands::List(_)
+                })
+                | Commands::Gifts(_)
+        )
+    }
+}
+
+#[derive(Args)]
+struct AirdropFunds {
+    #[clap(value_parser, long, default_value = "2")]
+    amount: Amount,
+    // airdrop only what is missing to reach this balance
+    #[clap(value_parser, long, conflicts_with = "amount")]
+    until_balance: Option<Amount>,
+    // RPC endpoint whose faucet serves the airdrop, e.g. a local test validator
+    #[clap(value_parser, long)]
+    faucet_url: Option<String>,
+}
+
+#[derive(Args)]
+struct CreateAndAirdropAccount {
+    #[clap(value_parser, long, default_value = "2")]
+    amount: Amount,
+    #[clap(value_parser, long)]
+    faucet_url: Option<String>,
+}
+
+#[derive(Args)]
+struct EnsureFunded {
+    // defaults to the signer and the U_PARTNER0_PRIV / U_PARTNER1_PRIV users
+    #[clap(value_parser)]
+    pubkeys: Vec<Pubkey>,
+    #[clap(value_parser, long, default_value = "2")]
+    min_balance: Amount,
+    #[clap(value_parser, long)]
+    faucet_url: Option<String>,
 }
 
 #[derive(Args)]
@@ -50,6 +181,8 @@ struct CancelWedding {
     partner0: Pubkey,
     #[clap(value_parser, long)]
     partner1: Pubkey,
+    #[clap(value_parser, long)]
+    yes: bool,
 }
 
 #[derive(Args)]
@@ -59,13 +192,22 @@ struct SetupPartner {
     #[clap(value_parser, long)]
     name: String,
     #[clap(value_parser, long)]
-    vows: String,
+    vows: Option<String>,
+    #[clap(value_parser, long, conflicts_with = "vows")]
+    vows_file: Option<PathBuf>,
+    #[clap(value_parser, long, conflicts_with_all = &["vows", "vows_file"])]
+    edit: bool,
+    // encrypt the vows so only these keys and the signer can read them
+    #[clap(value_parser, long, multiple_values = true)]
+    encrypt_vows_for: Vec<Pubkey>,
 }
 
 #[derive(Args)]
 struct ClosePartner {
     #[clap(value_parser, long)]
     other: Pubkey,
+    #[clap(value_parser, long)]
+    yes: bool,
 }
 
 #[derive(Args)]
@@ -75,7 +217,13 @@ struct UpdatePartner {
     #[clap(value_parser, long)]
     name: String,
     #[clap(value_parser, long)]
-    vows: String,
+    vows: Option<String>,
+    #[clap(value_parser, long, conflicts_with = "vows")]
+    vows_file: Option<PathBuf>,
+    #[clap(value_parser, long, conflicts_with_all = &["vows", "vows_file"])]
+    edit: bool,
+    #[clap(value_parser, long, multiple_values = true)]
+    encrypt_vows_for: Vec<Pubkey>,
 }
 
 #[derive(Args)]
@@ -91,7 +239,13 @@ struct UpdateVows {
     #[clap(value_parser, long)]
     other: Pubkey,
     #[clap(value_parser, long)]
-    vows: String,
+    vows: Option<String>,
+    #[clap(value_parser, long, conflicts_with = "vows")]
+    vows_file: Option<PathBuf>,
+    #[clap(value_parser, long, conflicts_with_all = &["vows", "vows_file"])]
+    edit: bool,
+    #[clap(value_parser, long, multiple_values = true)]
+    encrypt_vows_for: Vec<Pubkey>,
 }
 
 #[derive(Args)]
@@ -102,10 +256,41 @@ struct GiveAnswer {
     say_yes: bool,
 }
 
+#[derive(Args)]
+struct Propose {
+    #[clap(value_parser, long)]
+    other: Pubkey,
+    #[clap(value_parser, long)]
+    name: String,
+    #[clap(value_parser, long)]
+    vows: String,
+    #[clap(value_parser, long)]
+    say_yes: bool,
+}
+
 #[derive(Args)]
 struct Divorce {
     #[clap(value_parser, long)]
     other: Pubkey,
+    #[clap(value_parser, long)]
+    yes: bool,
+}
+
+#[derive(Args)]
+struct Ceremony {
+    // base58 private keys, falling back to U_PARTNER0_PRIV and U_PARTNER1_PRIV
+    #[clap(value_parser, long)]
+    partner0_priv: Option<String>,
+    #[clap(value_parser, long)]
+    partner1_priv: Option<String>,
+    #[clap(value_parser, long)]
+    name0: String,
+    #[clap(value_parser, long)]
+    vows0: String,
+    #[clap(value_parser, long)]
+    name1: String,
+    #[clap(value_parser, long)]
+    vows1: String,
 }
 
 #[derive(Args)]
@@ -130,6 +315,606 @@ struct WatchWedding {
     partner1: Pubkey,
 }
 
+#[derive(Args)]
+struct History {
+    #[clap(value_parser, long)]
+    partner0: Pubkey,
+    #[clap(value_parser, long)]
+    partner1: Pubkey,
+}
+
+#[derive(Args)]
+struct InspectTx {
+    #[clap(value_parser)]
+    signature: Signature,
+}
+
+#[derive(Args)]
+struct DecodeAccount {
+    // encoded account data, read from stdin when omitted or "-"
+    #[clap(value_parser)]
+    data: Option<String>,
+    #[clap(value_parser, long, conflicts_with = "data")]
+    file: Option<PathBuf>,
+    #[clap(value_enum, long, default_value = "base64")]
+    encoding: input::Encoding,
+}
+
+#[derive(Args)]
+struct Derive {
+    #[clap(subcommand)]
+    command: DeriveCommands,
+    #[clap(value_parser, long, global = true)]
+    json: bool,
+}
+
+#[derive(Subcommand)]
+enum DeriveCommands {
+    Wedding(DeriveWedding),
+    Partner(DerivePartner),
+}
+
+#[derive(Args)]
+struct DeriveWedding {
+    #[clap(value_parser, long)]
+    partner0: Pubkey,
+    #[clap(value_parser, long)]
+    partner1: Pubkey,
+}
+
+#[derive(Args)]
+struct DerivePartner {
+    #[clap(value_parser, long)]
+    partner: Pubkey,
+}
+
+#[derive(Args)]
+struct Balances {
+    #[clap(value_parser, long)]
+    partner0: Pubkey,
+    #[clap(value_parser, long)]
+    partner1: Pubkey,
+}
+
+#[derive(Args)]
+struct Fund {
+    #[clap(value_parser, long)]
+    to: Pubkey,
+    #[clap(value_parser, long)]
+    amount: Amount,
+    // treat amount as a target balance and only send the difference
+    #[clap(value_parser, long)]
+    top_up: bool,
+    #[clap(value_parser, long)]
+    memo: Option<String>,
+}
+
+#[derive(Args)]
+struct FundCouple {
+    #[clap(value_parser, long)]
+    partner0: Pubkey,
+    #[clap(value_parser, long)]
+    partner1: Pubkey,
+    #[clap(value_parser, long)]
+    amount: Amount,
+    #[clap(value_parser, long)]
+    top_up: bool,
+    #[clap(value_parser, long)]
+    memo: Option<String>,
+}
+
+#[derive(Args)]
+struct Teardown {
+    #[clap(value_parser, long)]
+    other: Pubkey,
+    #[clap(value_parser, long)]
+    to: Pubkey,
+    #[clap(value_parser, long)]
+    yes: bool,
+}
+
+#[derive(Args)]
+struct Certificate {
+    #[clap(value_parser, long)]
+    partner0: Pubkey,
+    #[clap(value_parser, long)]
+    partner1: Pubkey,
+    #[clap(value_enum, long, default_value = "html")]
+    format: certificate::Format,
+    // defaults to certificate.<format> in the current directory
+    #[clap(value_parser, long)]
+    output: Option<PathBuf>,
+    // a guest book from `guestbook collect` to include
+    #[clap(value_parser, long)]
+    guestbook: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct Qr {
+    // explorer link for a transaction
+    #[clap(value_parser, long, conflicts_with_all = &["partner0", "url"])]
+    tx: Option<Signature>,
+    // explorer link for the wedding account of both partners
+    #[clap(value_parser, long, requires = "partner1", conflicts_with = "url")]
+    partner0: Option<Pubkey>,
+    #[clap(value_parser, long, requires = "partner0")]
+    partner1: Option<Pubkey>,
+    #[clap(value_parser, long)]
+    url: Option<String>,
+    #[clap(value_enum, long, default_value = "terminal")]
+    format: qr::QrFormat,
+    // defaults to qr.<format> in the current directory for png and svg
+    #[clap(value_parser, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct ProveMarriage {
+    #[clap(value_parser, long)]
+    other: Pubkey,
+    // defaults to proof.json in the current directory
+    #[clap(value_parser, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct VerifyProof {
+    #[clap(value_parser)]
+    proof: PathBuf,
+    // also confirm the transactions and compare the accounts with the chain
+    #[clap(value_parser, long)]
+    check_chain: bool,
+}
+
+#[derive(Args)]
+struct SignMessage {
+    #[clap(value_parser, long)]
+    other: Pubkey,
+    // the text to sign, "-" or neither option reads stdin
+    #[clap(value_parser, long, conflicts_with = "message_file")]
+    message: Option<String>,
+    #[clap(value_parser, long)]
+    message_file: Option<PathBuf>,
+    // defaults to printing the signed message
+    #[clap(value_parser, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct VerifyMessage {
+    #[clap(value_parser)]
+    message: PathBuf,
+    // also check the partner account still belongs to the signed wedding
+    #[clap(value_parser, long)]
+    check_chain: bool,
+}
+
+#[derive(Args)]
+struct CommitVows {
+    #[clap(value_parser, long)]
+    other: Pubkey,
+    // sets up the partner account when it does not exist yet
+    #[clap(value_parser, long)]
+    name: Option<String>,
+    #[clap(value_parser, long)]
+    vows: Option<String>,
+    #[clap(value_parser, long, conflicts_with = "vows")]
+    vows_file: Option<PathBuf>,
+    #[clap(value_parser, long, conflicts_with_all = &["vows", "vows_file"])]
+    edit: bool,
+    // where the vows and salt are kept until the reveal, defaults to the home directory
+    #[clap(value_parser, long)]
+    output: Option<PathBuf>,
+    // replace an existing vows and salt file, losing the salt of its commitment
+    #[clap(value_parser, long)]
+    force: bool,
+}
+
+#[derive(Args)]
+struct RevealVows {
+    #[clap(value_parser, long)]
+    other: Pubkey,
+    // the file written by commit-vows, defaults to the one in the home directory
+    #[clap(value_parser, long)]
+    file: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct VerifyVows {
+    #[clap(value_parser, long)]
+    partner: Pubkey,
+    #[clap(value_parser, long)]
+    other: Pubkey,
+}
+
+#[derive(Args)]
+struct AnchorDocument {
+    #[clap(value_parser)]
+    file: PathBuf,
+    #[clap(value_parser, long)]
+    other: Pubkey,
+}
+
+#[derive(Args)]
+struct VerifyDocument {
+    #[clap(value_parser)]
+    file: PathBuf,
+    #[clap(value_parser, long)]
+    partner0: Pubkey,
+    #[clap(value_parser, long)]
+    partner1: Pubkey,
+}
+
+#[derive(Args)]
+struct Guestbook {
+    #[clap(subcommand)]
+    command: GuestbookCommands,
+}
+
+#[derive(Subcommand)]
+enum GuestbookCommands {
+    Sign(GuestbookSign),
+    Collect(GuestbookCollect),
+    Verify(GuestbookVerify),
+    Render(GuestbookRender),
+}
+
+#[derive(Args)]
+struct GuestbookSign {
+    #[clap(value_parser, long)]
+    wedding: Pubkey,
+    // the well-wishes to sign, "-" or leaving it out reads stdin
+    #[clap(value_parser, long)]
+    message: Option<String>,
+    #[clap(value_parser, long)]
+    name: Option<String>,
+    // defaults to printing the signed entry
+    #[clap(value_parser, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct GuestbookCollect {
+    // signed entries or guest books to merge
+    #[clap(value_parser, required = true)]
+    files: Vec<PathBuf>,
+    #[clap(value_parser, long)]
+    wedding: Pubkey,
+    // defaults to guestbook.json in the current directory
+    #[clap(value_parser, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct GuestbookVerify {
+    #[clap(value_parser)]
+    guestbook: PathBuf,
+    #[clap(value_parser, long)]
+    wedding: Pubkey,
+}
+
+#[derive(Args)]
+struct GuestbookRender {
+    #[clap(value_parser)]
+    guestbook: PathBuf,
+    #[clap(value_parser, long)]
+    wedding: Pubkey,
+}
+
+#[derive(Args)]
+struct Witness {
+    #[clap(subcommand)]
+    command: WitnessCommands,
+}
+
+#[derive(Subcommand)]
+enum WitnessCommands {
+    Attest(WitnessAttest),
+    List(WitnessList),
+}
+
+#[derive(Args)]
+struct WitnessAttest {
+    #[clap(value_parser, long)]
+    partner0: Pubkey,
+    #[clap(value_parser, long)]
+    partner1: Pubkey,
+    #[clap(value_parser, long, default_value = witness::DEFAULT_STATEMENT)]
+    statement: String,
+    // also send the attestation as a memo transaction in the wedding's history
+    #[clap(value_parser, long)]
+    submit: bool,
+    // defaults to printing the signed attestation
+    #[clap(value_parser, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct WitnessList {
+    #[clap(value_parser, long)]
+    partner0: Pubkey,
+    #[clap(value_parser, long)]
+    partner1: Pubkey,
+}
+
+#[derive(Args)]
+struct Gift {
+    #[clap(value_parser, long)]
+    partner0: Pubkey,
+    #[clap(value_parser, long)]
+    partner1: Pubkey,
+    // sent to each recipient, SOL unless --mint is given
+    #[clap(value_parser, long)]
+    amount: String,
+    // spl token mint, the amount is then in the token's decimals
+    #[clap(value_parser, long)]
+    mint: Option<Pubkey>,
+    #[clap(value_enum, long, default_value = "both")]
+    to: gift::GiftRecipient,
+    #[clap(value_parser, long)]
+    message: String,
+}
+
+#[derive(Args)]
+struct Gifts {
+    #[clap(subcommand)]
+    command: GiftsCommands,
+}
+
+#[derive(Subcommand)]
+enum GiftsCommands {
+    List(GiftsList),
+}
+
+#[derive(Args)]
+struct GiftsList {
+    #[clap(value_parser, long)]
+    partner0: Pubkey,
+    #[clap(value_parser, long)]
+    partner1: Pubkey,
+}
+
+#[derive(Args)]
+struct Estimate {
+    #[clap(subcommand)]
+    command: EstimateCommands,
+}
+
+#[derive(Subcommand)]
+enum EstimateCommands {
+    SetupWedding(SetupWedding),
+    CancelWedding(CancelWedding),
+    SetupPartner(SetupPartner),
+    ClosePartner(ClosePartner),
+    UpdatePartner(UpdatePartner),
+    UpdateName(UpdateName),
+    UpdateVows(UpdateVows),
+    GiveAnswer(GiveAnswer),
+    Propose(Propose),
+    Divorce(Divorce),
+}
+
+impl From<EstimateCommands> for Commands {
+    fn from(command: EstimateCommands) -> Commands {
+        match command {
+            EstimateCommands::SetupWedding(args) => Commands::SetupWedding(args),
+            EstimateCommands::CancelWedding(args) => Commands::CancelWedding(args),
+            EstimateCommands::SetupPartner(args) => Commands::SetupPartner(args),
+            EstimateCommands::ClosePartner(args) => Commands::ClosePartner(args),
+            EstimateCommands::UpdatePartner(args) => Commands::UpdatePartner(args),
+            EstimateCommands::UpdateName(args) => Commands::UpdateName(args),
+            EstimateCommands::UpdateVows(args) => Commands::UpdateVows(args),
+            EstimateCommands::GiveAnswer(args) => Commands::GiveAnswer(args),
+            EstimateCommands::Propose(args) => Commands::Propose(args),
+            EstimateCommands::Divorce(args) => Commands::Divorce(args),
+        }
+    }
+}
+
+fn check_bytes_left(name: &str, vows: &str, bytes_left: i64, space_left: i64) -> Result<()> {
+    println!(
+        "name: {} bytes, vows: {} bytes, {} bytes left in transaction, {} bytes left in partner account",
+        name.len(),
+        vows.len(),
+        bytes_left,
+        space_left
+    );
+
+    if space_left < 0 {
+        bail!(
+            "name and vows are {} bytes too long for the partner account",
+            -space_left
+        );
+    }
+    if bytes_left < 0 {
+        bail!(
+            "name and vows are {} bytes too long to fit in a single transaction",
+            -bytes_left
+        );
+    }
+
+    Ok(())
+}
+
+// bytes left in a transaction made of `ixs` once the priority fee instructions are added
+fn partner_bytes_left(payer: &Pubkey, ixs: &[Instruction]) -> Result<i64> {
+    let mut all_ixs = fee::priority_fee_ixs()?;
+    all_ixs.extend_from_slice(ixs);
+
+    Ok(util::tx_bytes_left(payer, &all_ixs))
+}
+
+// size of the signer's existing partner account, 0 when it has not been set up
+fn partner_size(partner: Option<&crypto_wedding::state::Partner>) -> usize {
+    partner
+        .map(|partner| util::partner_account_size(&partner.name, &partner.vows))
+        .unwrap_or(0)
+}
+
+fn run_estimate(command: Commands, signer: &Pubkey) -> Result<()> {
+    let estimate = match command {
+        Commands::SetupWedding(SetupWedding { partner0, partner1 }) => {
+            estimate::estimate_setup_wedding(signer, &partner0, &partner1)?
+        }
+        Commands::CancelWedding(CancelWedding {
+            partner0, partner1, ..
+        }) => estimate::estimate_cancel_wedding(signer, &partner0, &partner1)?,
+        Commands::SetupPartner(SetupPartner {
+            other,
+            name,
+            vows,
+            vows_file,
+            edit,
+            encrypt_vows_for,
+        }) => {
+            let vows = input::read_vows(vows, vows_file, edit, None)?;
+            let vows = placeholder_vows(signer, vows, &encrypt_vows_for);
+            estimate::estimate_setup_partner(signer, &other, &name, &vows)?
+        }
+        Commands::ClosePartner(ClosePartner { other, .. }) => {
+            estimate::estimate_close_partner(signer, &other)?
+        }
+        Commands::UpdatePartner(UpdatePartner {
+            other,
+            name,
+            vows,
+            vows_file,
+            edit,
+            encrypt_vows_for,
+        }) => {
+            let current = actions::get_partner_state(signer)
+                .ok()
+                .map(|partner| partner.vows);
+            let vows = input::read_vows(vows, vows_file, edit, current)?;
+            let vows = placeholder_vows(signer, vows, &encrypt_vows_for);
+            estimate::estimate_update_partner(signer, &other, Some(&name), Some(&vows))?
+        }
+        Commands::UpdateName(UpdateName { other, name }) => {
+            estimate::estimate_update_partner(signer, &other, Some(&name), None)?
+        }
+        Commands::UpdateVows(UpdateVows {
+            other,
+            vows,
+            vows_file,
+            edit,
+            encrypt_vows_for,
+        }) => {
+            let current = actions::get_partner_state(signer)
+                .ok()
+                .map(|partner| partner.vows);
+            let vows = input::read_vows(vows, vows_file, edit, current)?;
+            let vows = placeholder_vows(signer, vows, &encrypt_vows_for);
+            estimate::estimate_update_partner(signer, &other, None, Some(&vows))?
+        }
+        Commands::GiveAnswer(GiveAnswer { other, say_yes }) => {
+            estimate::estimate_give_answer(signer, &other, say_yes)?
+        }
+        Commands::Propose(Propose {
+            other,
+            name,
+            vows,
+            say_yes,
+        }) => estimate::estimate_propose(signer, &other, &name, &vows, say_yes)?,
+        Commands::Divorce(Divorce { other, .. }) => estimate::estimate_divorce(signer, &other)?,
+        _ => bail!("--estimate is not supported for this command"),
+    };
+
+    estimate.print();
+
+    Ok(())
+}
+
+// estimates run without the keypair, so encrypted vows are replaced by filler of the same length
+fn placeholder_vows(signer: &Pubkey, vows: String, encrypt_for: &[Pubkey]) -> String {
+    if encrypt_for.is_empty() {
+        return vows;
+    }
+
+    let recipients = encryption::recipients(signer, encrypt_for).len();
+    "x".repeat(encryption::encrypted_len(vows.len(), recipients))
+}
+
+fn read_signed_message(path: &PathBuf) -> Result<message::SignedMessage> {
+    let json: serde_json::Value = serde_json::from_str(&input::read_file(path)?)?;
+
+    message::SignedMessage::from_json(&json)
+}
+
+fn run_offline(command: Commands) -> Result<()> {
+    match command {
+        Commands::DecodeAccount(DecodeAccount {
+            data,
+            file,
+            encoding,
+        }) => {
+            let data = input::read_data(data, file, encoding)?;
+            let account = decode::decode_account(&data)?;
+
+            decode::print_account(&account);
+        }
+        Commands::Derive(Derive { command, json }) => {
+            let program_id = util::program_id();
+            match command {
+                DeriveCommands::Wedding(DeriveWedding { partner0, partner1 }) => {
+                    let derivation = derive::derive_wedding(&partner0, &partner1, &program_id);
+                    if json {
+                        println!("{}", serde_json::to_string_pretty(&derivation.to_json())?);
+                    } else {
+                        derivation.print();
+                    }
+                }
+                DeriveCommands::Partner(DerivePartner { partner }) => {
+                    let derivation = derive::derive_partner(&partner, &program_id);
+                    if json {
+                        println!("{}", serde_json::to_string_pretty(&derivation.to_json())?);
+                    } else {
+                        derivation.print();
+                    }
+                }
+            }
+        }
+        Commands::VerifyProof(VerifyProof { proof, .. }) => {
+            let proof: serde_json::Value = serde_json::from_str(&input::read_file(&proof)?)?;
+            let verified = proof::verify_proof(&proof)?;
+
+            proof::print_verified(&verified);
+        }
+        Commands::VerifyMessage(VerifyMessage { message, .. }) => {
+            let message = read_signed_message(&message)?;
+            message::verify_partner_message(&message)?;
+
+            message::print_message(&message);
+        }
+        Commands::Guestbook(Guestbook { command }) => match command {
+            GuestbookCommands::Collect(GuestbookCollect {
+                files,
+                wedding,
+                output,
+            }) => {
+                let entries = guestbook::collect(&files, &wedding)?;
+                let output = output.unwrap_or_else(|| PathBuf::from("guestbook.json"));
+                guestbook::save(&output, &entries)?;
+
+                println!("{} entries written to {}", entries.len(), output.display());
+            }
+            GuestbookCommands::Verify(GuestbookVerify { guestbook, wedding }) => {
+                let entries = guestbook::verify_guestbook(&guestbook::load(&guestbook)?, &wedding)?;
+
+                println!("all {} entries are valid for {:?}", entries.len(), wedding);
+            }
+            GuestbookCommands::Render(GuestbookRender { guestbook, wedding }) => {
+                let entries = guestbook::verify_guestbook(&guestbook::load(&guestbook)?, &wedding)?;
+
+                guestbook::print_guestbook(&wedding, &entries);
+            }
+            GuestbookCommands::Sign(_) => unreachable!("not an offline command"),
+        },
+        _ => unreachable!("not an offline command"),
+    };
+
+    Ok(())
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
     let path = format!(
@@ -137,50 +922,91 @@ fn main() -> Result<()> {
         dirs::home_dir().unwrap().to_str().unwrap()
     );
 
-    dotenv::from_path(path)?;
+    // set before loading the env file, which never overrides existing variables
+    if let Some(program_id) = cli.program_id {
+        env::set_var("PROGRAM_ID", program_id.to_string());
+    }
+
+    // the profile file is loaded first so its values win over the default env file
+    if let Some(profile) = &cli.profile {
+        env::set_var("PROFILE", profile);
+        let profile_path = format!("{}.{}", path, profile);
+        dotenv::from_path(&profile_path)
+            .with_context(|| format!("failed to load profile {}", profile_path))?;
+    }
+
+    let env_loaded = dotenv::from_path(path);
+    util::parse_program_id()?;
+    if cli.command.is_offline() {
+        return run_offline(cli.command);
+    }
+
+    env_loaded?;
     let signer = Keypair::from_base58_string(env::var("SIGNER_PRIV")?.as_ref());
     println!("operating as: {:?}", signer.pubkey());
 
+    if let Commands::Estimate(Estimate { command }) = cli.command {
+        return run_estimate(command.into(), &signer.pubkey());
+    }
+    if cli.estimate {
+        return run_estimate(cli.command, &signer.pubkey());
+    }
+
+    let policy = Policy::from_env()?;
+    if cli.command.is_airdrop() {
+        policy.check_airdrop()?;
+    }
+    if cli.command.is_mutating() {
+        policy.check_mutating(cli.i_know_this_is_mainnet, &[signer.pubkey()])?;
+    }
+
     match cli.command {
         Commands::GetOwnAccount => {
             println!("getting own account info...");
-            let rpc_client = RpcClient::new(network::RPC_URL);
+            let rpc_client = RpcClient::new(network::rpc_url());
             let signer_pub = signer.pubkey();
             let balance = rpc_client.get_balance(&signer_pub)?;
 
             println!("public key: {:?}", signer_pub);
-            println!("balance: {:?}", balance / util::LAMPORTS_PER_SOL);
+            println!("balance: {}", Amount::from_lamports(balance));
         }
-        Commands::AirdropFunds => {
+        Commands::AirdropFunds(AirdropFunds {
+            amount,
+            until_balance,
+            faucet_url,
+        }) => {
             println!("requesting airdrop...");
-            let rpc_client = RpcClient::new(network::RPC_URL);
+            let rpc_client = RpcClient::new(faucet_url.unwrap_or_else(network::rpc_url));
 
             let signer_pub = signer.pubkey();
-            let sig = network::request_airdrop(&rpc_client, &signer_pub, 2).unwrap();
-            let latest = rpc_client.get_latest_blockhash()?;
-            rpc_client.confirm_transaction_with_spinner(
-                &sig,
-                &latest,
-                CommitmentConfig::finalized(),
-            )?;
+            let sigs = match until_balance {
+                Some(target) => network::airdrop_until_balance(&rpc_client, &signer_pub, target)?,
+                None => network::request_airdrop(&rpc_client, &signer_pub, amount)?,
+            };
+            for sig in sigs {
+                println!(
+                    "tx: {:?}",
+                    util::get_tx_link(&sig, network::explorer_cluster().as_deref())
+                );
+            }
 
             let balance = rpc_client.get_balance(&signer_pub)?;
             println!(
-                "{:?} new balance: {:?}",
+                "{:?} new balance: {}",
                 signer_pub,
-                balance / util::LAMPORTS_PER_SOL
+                Amount::from_lamports(balance)
             );
         }
-        Commands::CreateAndAirdropAccount => {
+        Commands::CreateAndAirdropAccount(CreateAndAirdropAccount { amount, faucet_url }) => {
             println!("creating account...");
             let account = Keypair::new();
             let account_pub = account.pubkey();
             println!("account created");
 
             println!("requesting airdrop for new account...");
-            let rpc_client = RpcClient::new(network::RPC_URL);
+            let rpc_client = RpcClient::new(faucet_url.unwrap_or_else(network::rpc_url));
 
-            network::request_airdrop(&rpc_client, &account_pub, 2).unwrap();
+            network::request_airdrop(&rpc_client, &account_pub, amount)?;
 
             let balance = rpc_client.get_balance(&account_pub)?;
             println!("airdrop completed");
@@ -188,7 +1014,21 @@ fn main() -> Result<()> {
             println!("account created and funded:");
             println!("public key: {:?}", account_pub);
             println!("private key: {:?}", account.to_base58_string());
-            println!("balance: {:?}", balance / util::LAMPORTS_PER_SOL);
+            println!("balance: {}", Amount::from_lamports(balance));
+        }
+        Commands::EnsureFunded(EnsureFunded {
+            pubkeys,
+            min_balance,
+            faucet_url,
+        }) => {
+            let pubkeys = if pubkeys.is_empty() {
+                network::airdrop_users()?
+            } else {
+                pubkeys
+            };
+            let rpc_client = RpcClient::new(faucet_url.unwrap_or_else(network::rpc_url));
+
+            network::ensure_funded(&rpc_client, &pubkeys, min_balance)?;
         }
         Commands::SetupWedding(SetupWedding { partner0, partner1 }) => {
             println!("setting up wedding...");
@@ -196,39 +1036,125 @@ fn main() -> Result<()> {
 
             actions::print_wedding(&partner0, &partner1);
 
-            println!("tx: {:?}", util::get_tx_link(&sig, Some("devnet")));
+            println!(
+                "tx: {:?}",
+                util::get_tx_link(&sig, network::explorer_cluster().as_deref())
+            );
         }
-        Commands::CancelWedding(CancelWedding { partner0, partner1 }) => {
+        Commands::CancelWedding(CancelWedding {
+            partner0,
+            partner1,
+            yes,
+        }) => {
+            preview::confirm(&preview::preview_cancel_wedding(&partner0, &partner1)?, yes)?;
+
             println!("cancelling wedding...");
             let sig = actions::cancel_wedding(&signer, &partner0, &partner1)?;
 
             actions::print_wedding(&partner0, &partner1);
 
-            println!("tx: {:?}", util::get_tx_link(&sig, Some("devnet")));
+            println!(
+                "tx: {:?}",
+                util::get_tx_link(&sig, network::explorer_cluster().as_deref())
+            );
         }
-        Commands::SetupPartner(SetupPartner { other, name, vows }) => {
+        Commands::SetupPartner(SetupPartner {
+            other,
+            name,
+            vows,
+            vows_file,
+            edit,
+            encrypt_vows_for,
+        }) => {
+            let mut vows = input::read_vows(vows, vows_file, edit, None)?;
+            if !encrypt_vows_for.is_empty() {
+                vows = encryption::encrypt_vows(&signer, &encrypt_vows_for, &vows)?;
+            }
+            let bytes_left = partner_bytes_left(
+                &signer.pubkey(),
+                &[actions::setup_partner_ix(
+                    &signer.pubkey(),
+                    &other,
+                    &name,
+                    &vows,
+                )],
+            )?;
+            check_bytes_left(
+                &name,
+                &vows,
+                bytes_left,
+                util::partner_space_left(0, &name, &vows),
+            )?;
+
             println!("setting up partner PDA account...");
             let sig = actions::setup_partner(&signer, &other, &name, &vows)?;
 
             actions::print_partner(&signer.pubkey());
 
-            println!("tx: {:?}", util::get_tx_link(&sig, Some("devnet")));
+            println!(
+                "tx: {:?}",
+                util::get_tx_link(&sig, network::explorer_cluster().as_deref())
+            );
         }
-        Commands::ClosePartner(ClosePartner { other }) => {
+        Commands::ClosePartner(ClosePartner { other, yes }) => {
+            preview::confirm(
+                &preview::preview_close_partner(&signer.pubkey(), &other)?,
+                yes,
+            )?;
+
             println!("closing partner PDA account...");
             let sig = actions::close_partner(&signer, &other)?;
 
             actions::print_partner(&signer.pubkey());
 
-            println!("tx: {:?}", util::get_tx_link(&sig, Some("devnet")));
+            println!(
+                "tx: {:?}",
+                util::get_tx_link(&sig, network::explorer_cluster().as_deref())
+            );
         }
-        Commands::UpdatePartner(UpdatePartner { other, name, vows }) => {
+        Commands::UpdatePartner(UpdatePartner {
+            other,
+            name,
+            vows,
+            vows_file,
+            edit,
+            encrypt_vows_for,
+        }) => {
+            // encrypted vows are decrypted to prefill the editor
+            let partner = actions::get_partner_state(&signer.pubkey()).ok();
+            let current = partner.as_ref().map(|partner| {
+                encryption::decrypt_vows(&signer, &partner.user, &partner.vows)
+                    .unwrap_or_else(|_| partner.vows.clone())
+            });
+            let mut vows = input::read_vows(vows, vows_file, edit, current)?;
+            if !encrypt_vows_for.is_empty() {
+                vows = encryption::encrypt_vows(&signer, &encrypt_vows_for, &vows)?;
+            }
+            let bytes_left = partner_bytes_left(
+                &signer.pubkey(),
+                &[actions::update_partner_ix(
+                    &signer.pubkey(),
+                    &other,
+                    &name,
+                    &vows,
+                )],
+            )?;
+            check_bytes_left(
+                &name,
+                &vows,
+                bytes_left,
+                util::partner_space_left(partner_size(partner.as_ref()), &name, &vows),
+            )?;
+
             println!("updating partner PDA account...");
             let sig = actions::update_partner(&signer, &other, name.as_ref(), vows.as_ref())?;
 
             actions::print_partner(&signer.pubkey());
 
-            println!("tx: {:?}", util::get_tx_link(&sig, Some("devnet")));
+            println!(
+                "tx: {:?}",
+                util::get_tx_link(&sig, network::explorer_cluster().as_deref())
+            );
         }
         Commands::UpdateName(UpdateName { other, name }) => {
             println!("updating name on partner PDA account...");
@@ -236,15 +1162,52 @@ fn main() -> Result<()> {
 
             actions::print_partner(&signer.pubkey());
 
-            println!("tx: {:?}", util::get_tx_link(&sig, Some("devnet")));
+            println!(
+                "tx: {:?}",
+                util::get_tx_link(&sig, network::explorer_cluster().as_deref())
+            );
         }
-        Commands::UpdateVows(UpdateVows { other, vows }) => {
+        Commands::UpdateVows(UpdateVows {
+            other,
+            vows,
+            vows_file,
+            edit,
+            encrypt_vows_for,
+        }) => {
+            // encrypted vows are decrypted to prefill the editor
+            let partner = actions::get_partner_state(&signer.pubkey()).ok();
+            let current = partner.as_ref().map(|partner| {
+                encryption::decrypt_vows(&signer, &partner.user, &partner.vows)
+                    .unwrap_or_else(|_| partner.vows.clone())
+            });
+            let mut vows = input::read_vows(vows, vows_file, edit, current)?;
+            if !encrypt_vows_for.is_empty() {
+                vows = encryption::encrypt_vows(&signer, &encrypt_vows_for, &vows)?;
+            }
+            let name = partner
+                .as_ref()
+                .map(|partner| partner.name.clone())
+                .unwrap_or_default();
+            let bytes_left = partner_bytes_left(
+                &signer.pubkey(),
+                &[actions::update_vows_ix(&signer.pubkey(), &other, &vows)],
+            )?;
+            check_bytes_left(
+                "",
+                &vows,
+                bytes_left,
+                util::partner_space_left(partner_size(partner.as_ref()), &name, &vows),
+            )?;
+
             println!("updating vows on partner PDA account...");
             let sig = actions::update_vows(&signer, &other, vows.as_ref())?;
 
             actions::print_partner(&signer.pubkey());
 
-            println!("tx: {:?}", util::get_tx_link(&sig, Some("devnet")));
+            println!(
+                "tx: {:?}",
+                util::get_tx_link(&sig, network::explorer_cluster().as_deref())
+            );
         }
         Commands::GiveAnswer(GiveAnswer { other, say_yes }) => {
             println!("giving answer on partner PDA account...");
@@ -252,25 +1215,464 @@ fn main() -> Result<()> {
 
             actions::print_partner(&signer.pubkey());
 
-            println!("tx: {:?}", util::get_tx_link(&sig, Some("devnet")));
+            println!(
+                "tx: {:?}",
+                util::get_tx_link(&sig, network::explorer_cluster().as_deref())
+            );
+        }
+        Commands::Propose(Propose {
+            other,
+            name,
+            vows,
+            say_yes,
+        }) => {
+            // the wedding, partner account and answer all go in one transaction
+            let bytes_left = partner_bytes_left(
+                &signer.pubkey(),
+                &actions::propose_ixs(&signer.pubkey(), &other, &name, &vows, say_yes),
+            )?;
+            check_bytes_left(
+                &name,
+                &vows,
+                bytes_left,
+                util::partner_space_left(0, &name, &vows),
+            )?;
+
+            println!("proposing...");
+            let sig = actions::propose(&signer, &other, &name, &vows, say_yes)?;
+
+            actions::print_wedding(&signer.pubkey(), &other);
+            actions::print_partner(&signer.pubkey());
+
+            println!(
+                "tx: {:?}",
+                util::get_tx_link(&sig, network::explorer_cluster().as_deref())
+            );
         }
-        Commands::Divorce(Divorce { other }) => {
+        Commands::Divorce(Divorce { other, yes }) => {
+            preview::confirm(&preview::preview_divorce(&signer.pubkey(), &other)?, yes)?;
+
             println!("divorcing...");
             let sig = actions::divorce(&signer, &other)?;
 
             actions::print_wedding(&signer.pubkey(), &other);
 
-            println!("tx: {:?}", util::get_tx_link(&sig, Some("devnet")));
+            println!(
+                "tx: {:?}",
+                util::get_tx_link(&sig, network::explorer_cluster().as_deref())
+            );
+        }
+        Commands::Ceremony(Ceremony {
+            partner0_priv,
+            partner1_priv,
+            name0,
+            vows0,
+            name1,
+            vows1,
+        }) => {
+            let partner0_priv = match partner0_priv {
+                Some(key) => key,
+                None => env::var("U_PARTNER0_PRIV")?,
+            };
+            let partner1_priv = match partner1_priv {
+                Some(key) => key,
+                None => env::var("U_PARTNER1_PRIV")?,
+            };
+            let partner0 = Keypair::from_base58_string(partner0_priv.as_ref());
+            let partner1 = Keypair::from_base58_string(partner1_priv.as_ref());
+            policy.check_mutating(
+                cli.i_know_this_is_mainnet,
+                &[partner0.pubkey(), partner1.pubkey()],
+            )?;
+
+            println!("running ceremony...");
+            let steps = ceremony::run_ceremony(
+                &signer,
+                &ceremony::CeremonyPartner {
+                    keypair: &partner0,
+                    name: &name0,
+                    vows: &vows0,
+                },
+                &ceremony::CeremonyPartner {
+                    keypair: &partner1,
+                    name: &name1,
+                    vows: &vows1,
+                },
+            )?;
+
+            ceremony::print_report(&partner0.pubkey(), &partner1.pubkey(), &steps);
         }
         Commands::PrintWedding(PrintWedding { partner0, partner1 }) => {
             actions::print_wedding(&partner0, &partner1);
         }
         Commands::PrintPartner(PrintPartner { partner }) => {
             actions::print_partner(&partner);
+            encryption::print_decrypted_vows(&signer, &partner);
         }
         Commands::WatchWedding(WatchWedding { partner0, partner1 }) => {
             actions::watch_wedding(&partner0, &partner1)?;
         }
+        Commands::History(History { partner0, partner1 }) => {
+            println!("fetching wedding history...");
+            let history = history::get_history(&partner0, &partner1)?;
+
+            history::print_history(&partner0, &partner1, &history);
+        }
+        Commands::InspectTx(InspectTx { signature }) => {
+            let rpc_client = RpcClient::new(network::rpc_url());
+            let fetched = history::get_transaction(&rpc_client, &signature)?;
+
+            inspect::print_transaction(&fetched);
+        }
+        Commands::DecodeAccount(_) | Commands::Derive(_) => {
+            unreachable!("handled by run_offline")
+        }
+        Commands::Balances(Balances { partner0, partner1 }) => {
+            actions::print_balances(&signer.pubkey(), &partner0, &partner1)?;
+        }
+        Commands::Fund(Fund {
+            to,
+            amount,
+            top_up,
+            memo,
+        }) => {
+            println!("funding {:?}...", to);
+            match transfer::fund(&signer, &[to], amount, top_up, memo.as_deref())? {
+                Some(sig) => println!(
+                    "tx: {:?}",
+                    util::get_tx_link(&sig, network::explorer_cluster().as_deref())
+                ),
+                None => println!("nothing to send"),
+            }
+        }
+        Commands::FundCouple(FundCouple {
+            partner0,
+            partner1,
+            amount,
+            top_up,
+            memo,
+        }) => {
+            println!("funding {:?} and {:?}...", partner0, partner1);
+            match transfer::fund(
+                &signer,
+                &[partner0, partner1],
+                amount,
+                top_up,
+                memo.as_deref(),
+            )? {
+                Some(sig) => println!(
+                    "tx: {:?}",
+                    util::get_tx_link(&sig, network::explorer_cluster().as_deref())
+                ),
+                None => println!("nothing to send"),
+            }
+        }
+        Commands::Teardown(Teardown { other, to, yes }) => {
+            let signer_pub = signer.pubkey();
+            let plan = teardown::plan_teardown(&signer_pub, &other);
+            teardown::print_plan(&signer_pub, &other, &to, &plan)?;
+            preview::confirm_action("teardown", yes)?;
+
+            for (step, sig) in teardown::run_teardown(&signer, &other, &to, &plan)? {
+                println!(
+                    "{}: {:?}",
+                    step,
+                    util::get_tx_link(&sig, network::explorer_cluster().as_deref())
+                );
+            }
+        }
+        Commands::Certificate(Certificate {
+            partner0,
+            partner1,
+            format,
+            output,
+            guestbook,
+        }) => {
+            println!("building certificate...");
+            let mut certificate = certificate::build_certificate(&partner0, &partner1)?;
+            if let Some(guestbook) = guestbook {
+                certificate.guestbook = guestbook::verify_guestbook(
+                    &guestbook::load(&guestbook)?,
+                    &certificate.wedding,
+                )?;
+            }
+            let output = output
+                .unwrap_or_else(|| PathBuf::from(format!("certificate.{}", format.extension())));
+            std::fs::write(&output, certificate::render(&certificate, format)?)?;
+
+            println!("certificate written to {}", output.display());
+        }
+        Commands::Qr(Qr {
+            tx,
+            partner0,
+            partner1,
+            url,
+            format,
+            output,
+        }) => {
+            let cluster = network::explorer_cluster();
+            let link = match (tx, partner0, partner1, url) {
+                (Some(sig), _, _, _) => util::get_tx_link(&sig, cluster.as_deref()),
+                (_, Some(partner0), Some(partner1), _) => util::get_account_link(
+                    &util::find_wedding_pda(&partner0, &partner1),
+                    cluster.as_deref(),
+                ),
+                (_, _, _, Some(url)) => url,
+                _ => bail!("one of --tx, --partner0 and --partner1, or --url is required"),
+            };
+
+            println!("{}", link);
+            match format {
+                qr::QrFormat::Terminal => println!("{}", qr::render_terminal(&link)?),
+                qr::QrFormat::Png => {
+                    let output = output.unwrap_or_else(|| PathBuf::from("qr.png"));
+                    qr::save_png(&link, &output)?;
+                    println!("qr code written to {}", output.display());
+                }
+                qr::QrFormat::Svg => {
+                    let output = output.unwrap_or_else(|| PathBuf::from("qr.svg"));
+                    std::fs::write(&output, qr::render_svg(&link)?)?;
+                    println!("qr code written to {}", output.display());
+                }
+            }
+        }
+        Commands::ProveMarriage(ProveMarriage { other, output }) => {
+            println!("building proof of marriage...");
+            let proof = proof::prove_marriage(&signer, &other)?;
+            let output = output.unwrap_or_else(|| PathBuf::from("proof.json"));
+            std::fs::write(&output, serde_json::to_string_pretty(&proof)?)?;
+
+            println!("proof written to {}", output.display());
+        }
+        Commands::VerifyProof(VerifyProof { proof, .. }) => {
+            let proof: serde_json::Value = serde_json::from_str(&input::read_file(&proof)?)?;
+            let verified = proof::verify_proof(&proof)?;
+
+            proof::print_verified(&verified);
+            proof::check_proof_on_chain(&proof, &verified)?;
+        }
+        Commands::SignMessage(SignMessage {
+            other,
+            message,
+            message_file,
+            output,
+        }) => {
+            let text = match (message, message_file) {
+                (Some(text), None) if text != "-" => text,
+                (None, Some(path)) => input::read_file(&path)?,
+                _ => input::read_stdin()?,
+            };
+            let signed = message::sign_partner_message(&signer, &other, &text)?;
+            let json = serde_json::to_string_pretty(&signed.to_json())?;
+
+            match output {
+                Some(output) => {
+                    std::fs::write(&output, json)?;
+                    println!("signed message written to {}", output.display());
+                }
+                None => println!("{}", json),
+            }
+        }
+        Commands::VerifyMessage(VerifyMessage { message, .. }) => {
+            let message = read_signed_message(&message)?;
+            message::verify_partner_message(&message)?;
+
+            message::print_message(&message);
+            message::check_partner_message_on_chain(&message)?;
+        }
+        Commands::CommitVows(CommitVows {
+            other,
+            name,
+            vows,
+            vows_file,
+            edit,
+            output,
+            force,
+        }) => {
+            let vows = input::read_vows(vows, vows_file, edit, None)?;
+            let saved = commitment::VowsCommitment::new(&signer.pubkey(), &other, vows);
+            let committed = saved.commitment();
+
+            // saved before sending, the commitment is useless once the salt is lost
+            let output = output.unwrap_or_else(|| commitment::default_path(&saved.wedding));
+            saved.save(&output, force)?;
+            println!("vows and salt saved to {}", output.display());
+
+            println!("committing to vows on partner PDA account...");
+            let sig = match (actions::get_partner_state(&signer.pubkey()), name) {
+                (Ok(_), _) => actions::update_vows(&signer, &other, &committed)?,
+                (Err(_), Some(name)) => actions::setup_partner(&signer, &other, &name, &committed)?,
+                (Err(_), None) => bail!("no partner account yet, --name is required to set it up"),
+            };
+
+            actions::print_partner(&signer.pubkey());
+
+            println!(
+                "tx: {:?}",
+                util::get_tx_link(&sig, network::explorer_cluster().as_deref())
+            );
+        }
+        Commands::RevealVows(RevealVows { other, file }) => {
+            let path = file.unwrap_or_else(|| {
+                commitment::default_path(&util::find_wedding_pda(&signer.pubkey(), &other))
+            });
+            let saved = commitment::VowsCommitment::load(&path)?;
+            if saved.partner != signer.pubkey() {
+                bail!(
+                    "{} holds vows committed by {}",
+                    path.display(),
+                    saved.partner
+                );
+            }
+            let partner = actions::get_partner_state(&signer.pubkey())?;
+            commitment::check_revealable(&saved, &partner.vows)?;
+
+            let memo = commitment::reveal_memo(&saved.salt);
+            let bytes_left = partner_bytes_left(
+                &signer.pubkey(),
+                &[
+                    spl_memo::build_memo(memo.as_bytes(), &[&signer.pubkey()]),
+                    actions::update_vows_ix(&signer.pubkey(), &other, &saved.vows),
+                ],
+            )?;
+            check_bytes_left(
+                "",
+                &saved.vows,
+                bytes_left,
+                util::partner_space_left(
+                    util::partner_account_size(&partner.name, &partner.vows),
+                    &partner.name,
+                    &saved.vows,
+                ),
+            )?;
+
+            println!("revealing vows on partner PDA account...");
+            let sig = actions::update_vows_with_memo(&signer, &other, &saved.vows, Some(&memo))?;
+
+            actions::print_partner(&signer.pubkey());
+
+            println!(
+                "tx: {:?}",
+                util::get_tx_link(&sig, network::explorer_cluster().as_deref())
+            );
+        }
+        Commands::VerifyVows(VerifyVows { partner, other }) => {
+            let checks = commitment::verify_reveals(&partner, &other)?;
+
+            commitment::print_reveal_checks(&partner, &checks);
+        }
+        Commands::AnchorDocument(AnchorDocument { file, other }) => {
+            let hash = document::hash_file(&file)?;
+            println!("sha256: {}", hash);
+
+            println!("anchoring document in the wedding history...");
+            let sig = document::anchor_document(&signer, &other, &hash)?;
+
+            println!(
+                "tx: {:?}",
+                util::get_tx_link(&sig, network::explorer_cluster().as_deref())
+            );
+        }
+        Commands::VerifyDocument(VerifyDocument {
+            file,
+            partner0,
+            partner1,
+        }) => {
+            let hash = document::hash_file(&file)?;
+            let anchorings = document::find_anchorings(&partner0, &partner1, &hash)?;
+
+            document::print_anchorings(&hash, &anchorings);
+            document::check_anchored(&anchorings)?;
+        }
+        Commands::Guestbook(Guestbook {
+            command:
+                GuestbookCommands::Sign(GuestbookSign {
+                    wedding,
+                    message,
+                    name,
+                    output,
+                }),
+        }) => {
+            let text = match message {
+                Some(text) if text != "-" => text,
+                _ => input::read_stdin()?,
+            };
+            let entry = guestbook::sign_entry(&signer, &wedding, name.as_deref(), &text);
+            let json = serde_json::to_string_pretty(&entry.to_json())?;
+
+            match output {
+                Some(output) => {
+                    std::fs::write(&output, json)?;
+                    println!("guestbook entry written to {}", output.display());
+                }
+                None => println!("{}", json),
+            }
+        }
+        Commands::Guestbook(_) => unreachable!("handled by run_offline"),
+        Commands::Witness(Witness { command }) => match command {
+            WitnessCommands::Attest(WitnessAttest {
+                partner0,
+                partner1,
+                statement,
+                submit,
+                output,
+            }) => {
+                let attestation = witness::attest(&signer, &partner0, &partner1, &statement)?;
+                let json = serde_json::to_string_pretty(&attestation.to_json())?;
+
+                match output {
+                    Some(output) => {
+                        std::fs::write(&output, json)?;
+                        println!("attestation written to {}", output.display());
+                    }
+                    None => println!("{}", json),
+                }
+
+                if submit {
+                    println!("submitting attestation to the wedding history...");
+                    let sig = witness::submit(&signer, &attestation)?;
+
+                    println!(
+                        "tx: {:?}",
+                        util::get_tx_link(&sig, network::explorer_cluster().as_deref())
+                    );
+                }
+            }
+            WitnessCommands::List(WitnessList { partner0, partner1 }) => {
+                let listed = witness::list_attestations(&partner0, &partner1)?;
+
+                witness::print_attestations(&util::find_wedding_pda(&partner0, &partner1), &listed);
+            }
+        },
+        Commands::Gift(Gift {
+            partner0,
+            partner1,
+            amount,
+            mint,
+            to,
+            message,
+        }) => {
+            let rpc_client = RpcClient::new(network::rpc_url());
+            let amount = gift::parse_gift_amount(&rpc_client, &amount, mint.as_ref())?;
+            let recipients = to.pubkeys(&partner0, &partner1);
+
+            println!("sending {} to {} recipient(s)...", amount, recipients.len());
+            let sig =
+                gift::send_gift(&signer, &partner0, &partner1, &recipients, amount, &message)?;
+
+            println!(
+                "tx: {:?}",
+                util::get_tx_link(&sig, network::explorer_cluster().as_deref())
+            );
+        }
+        Commands::Gifts(Gifts {
+            command: GiftsCommands::List(GiftsList { partner0, partner1 }),
+        }) => {
+            let gifts = gift::list_gifts(&partner0, &partner1)?;
+
+            gift::print_gifts(&partner0, &partner1, &gifts);
+        }
+        Commands::Estimate(_) => unreachable!("handled by run_estimate"),
     };
 
     Ok(())
@@ -0,0 +1,182 @@
+use crate::{actions, util};
+use anyhow::{anyhow, bail, Result};
+use serde_json::{json, Value};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::{keypair::Keypair, Signer},
+};
+use std::{collections::BTreeMap, str::FromStr};
+
+pub const PARTNER_MESSAGE_HEADER: &str = "crypto wedding partner message";
+
+// an off-chain ed25519 signature over a text payload of "key: value" header lines followed by
+// a blank line and the free form message
+pub struct SignedMessage {
+    pub payload: String,
+    pub signer: Pubkey,
+    pub signature: Signature,
+}
+
+impl SignedMessage {
+    pub fn sign(signer: &Keypair, payload: String) -> SignedMessage {
+        let signature = signer.sign_message(payload.as_bytes());
+
+        SignedMessage {
+            payload,
+            signer: signer.pubkey(),
+            signature,
+        }
+    }
+
+    pub fn verify(&self) -> Result<()> {
+        if !self
+            .signature
+            .verify(self.signer.as_ref(), self.payload.as_bytes())
+        {
+            bail!("signature does not match the message and signer");
+        }
+
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "payload": self.payload,
+            "signer": self.signer.to_string(),
+            "signature": self.signature.to_string(),
+        })
+    }
+
+    pub fn from_json(value: &Value) -> Result<SignedMessage> {
+        let field = |key: &str| {
+            value
+                .get(key)
+                .and_then(Value::as_str)
+                .ok_or_else(|| anyhow!("signed message is missing {}", key))
+        };
+
+        Ok(SignedMessage {
+            payload: field("payload")?.to_string(),
+            signer: Pubkey::from_str(field("signer")?)?,
+            signature: Signature::from_str(field("signature")?)?,
+        })
+    }
+
+    // the header lines of the payload, the first line is the kind of message
+    pub fn headers(&self) -> BTreeMap<String, String> {
+        self.payload
+            .lines()
+            .skip(1)
+            .take_while(|line| !line.is_empty())
+            .filter_map(|line| line.split_once(": "))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    pub fn kind(&self) -> &str {
+        self.payload.lines().next().unwrap_or_default()
+    }
+
+    pub fn header_pubkey(&self, key: &str) -> Result<Pubkey> {
+        let headers = self.headers();
+        let value = headers
+            .get(key)
+            .ok_or_else(|| anyhow!("signed message has no {} header", key))?;
+
+        Ok(Pubkey::from_str(value)?)
+    }
+
+    pub fn body(&self) -> &str {
+        match self.payload.split_once("\n\n") {
+            Some((_, body)) => body,
+            None => "",
+        }
+    }
+}
+
+pub fn build_payload(kind: &str, headers: &[(&str, String)], body: &str) -> String {
+    let mut payload = format!("{}\n", kind);
+    for (key, value) in headers {
+        payload.push_str(&format!("{}: {}\n", key, value));
+    }
+    payload.push('\n');
+    payload.push_str(body);
+
+    payload
+}
+
+// signs `text` as a partner, binding the partner account, wedding and its current status
+pub fn sign_partner_message(signer: &Keypair, other: &Pubkey, text: &str) -> Result<SignedMessage> {
+    let signer_pub = signer.pubkey();
+    let wedding = actions::get_wedding_state(&signer_pub, other)?;
+    actions::get_partner_state(&signer_pub)?;
+
+    let payload = build_payload(
+        PARTNER_MESSAGE_HEADER,
+        &[
+            ("program", util::program_id().to_string()),
+            ("signer", signer_pub.to_string()),
+            ("partner", util::find_partner_pda(&signer_pub).to_string()),
+            (
+                "wedding",
+                util::find_wedding_pda(&signer_pub, other).to_string(),
+            ),
+            ("status", format!("{:?}", wedding.status)),
+        ],
+        text,
+    );
+
+    Ok(SignedMessage::sign(signer, payload))
+}
+
+// offline checks: the signature and that the partner account is the signer's PDA
+pub fn verify_partner_message(message: &SignedMessage) -> Result<()> {
+    if message.kind() != PARTNER_MESSAGE_HEADER {
+        bail!("not a partner message: {}", message.kind());
+    }
+    message.verify()?;
+
+    if message.header_pubkey("signer")? != message.signer {
+        bail!("signer header does not match the signing key");
+    }
+
+    let program_id = message.header_pubkey("program")?;
+    let (partner, _) = util::find_partner_pda_with_bump(&message.signer, &program_id);
+    if message.header_pubkey("partner")? != partner {
+        bail!("partner account is not derived from the signer");
+    }
+
+    Ok(())
+}
+
+// online checks: the partner account still belongs to the signed wedding, and reports whether
+// the wedding status has changed since signing
+pub fn check_partner_message_on_chain(message: &SignedMessage) -> Result<()> {
+    let wedding_address = message.header_pubkey("wedding")?;
+    let partner = actions::get_partner_state(&message.signer)?;
+    if partner.wedding != wedding_address {
+        bail!("partner account now belongs to wedding {}", partner.wedding);
+    }
+
+    let wedding: crypto_wedding::state::Wedding =
+        util::get_crypto_wedding(None).account(wedding_address)?;
+    let status = format!("{:?}", wedding.status);
+    match message.headers().get("status") {
+        Some(signed) if signed == &status => println!("wedding status unchanged: {}", status),
+        _ => println!("wedding status has changed since signing, now: {}", status),
+    }
+
+    Ok(())
+}
+
+pub fn print_message(message: &SignedMessage) {
+    println!("---| signed message |---");
+    println!("signed by: {:?}", message.signer);
+    for (key, value) in message.headers() {
+        println!("{}: {}", key, value);
+    }
+    println!("message:");
+    println!("{}", message.body());
+    println!("------------------------");
+}
@@ -1,97 +1,229 @@
-use crate::util;
+use crate::{amount::Amount, util};
 use anchor_client::{Client, Cluster};
-use solana_client::rpc_client::RpcClient;
+use anyhow::Result;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    rpc_client::RpcClient,
+};
 use solana_sdk::{
     commitment_config::CommitmentConfig,
     pubkey::Pubkey,
     signature::Signature,
     signer::{keypair::Keypair, null_signer::NullSigner, Signer},
 };
-use std::error::Error;
-use std::{env, rc::Rc};
+use std::{env, rc::Rc, thread, time::Duration};
 
 pub const RPC_URL: &str = "https://api.devnet.solana.com";
 pub const WS_URL: &str = "wss://api.devnet.solana.com";
 
+// RPC_URL and WS_URL can be overridden in ~/.crypto_wedding_cli_env, defaulting to devnet
+pub fn rpc_url() -> String {
+    env::var("RPC_URL").unwrap_or_else(|_| RPC_URL.into())
+}
+
+pub fn ws_url() -> String {
+    match env::var("WS_URL") {
+        Ok(url) => url,
+        Err(_) => match env::var("RPC_URL") {
+            Ok(url) => url.replacen("http", "ws", 1),
+            Err(_) => WS_URL.into(),
+        },
+    }
+}
+
+pub const MAINNET_GENESIS_HASH: &str = "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d";
+
+// asks the cluster for its genesis hash, any url can point at mainnet
+pub fn is_mainnet() -> Result<bool> {
+    let rpc_client = RpcClient::new(rpc_url());
+
+    Ok(rpc_client.get_genesis_hash()?.to_string() == MAINNET_GENESIS_HASH)
+}
+
+// guessed from the url, only good enough for display
+fn is_mainnet_url(url: &str) -> bool {
+    url.contains("mainnet")
+}
+
+// human readable cluster name, the rpc url for custom clusters
+pub fn cluster_name() -> String {
+    let url = rpc_url();
+    if is_mainnet_url(&url) {
+        "mainnet-beta".into()
+    } else if url.contains("devnet") {
+        "devnet".into()
+    } else if url.contains("testnet") {
+        "testnet".into()
+    } else {
+        url
+    }
+}
+
+// cluster query parameter for explorer links, none for mainnet
+pub fn explorer_cluster() -> Option<String> {
+    let url = rpc_url();
+    if is_mainnet_url(&url) {
+        None
+    } else if url.contains("devnet") {
+        Some("devnet".into())
+    } else if url.contains("testnet") {
+        Some("testnet".into())
+    } else {
+        Some(format!("custom&customUrl={}", url))
+    }
+}
+
 pub fn get_client(signer: Option<&Keypair>) -> Client {
     match signer {
         Some(signer) => {
             // TODO: is there not a better way to do this?
             let signer = Keypair::from_bytes(signer.to_bytes().as_ref()).unwrap();
             Client::new_with_options(
-                Cluster::Custom(RPC_URL.into(), WS_URL.into()),
+                Cluster::Custom(rpc_url(), ws_url()),
                 Rc::new(signer),
                 CommitmentConfig::confirmed(),
             )
         }
         None => Client::new_with_options(
-            Cluster::Custom(RPC_URL.into(), WS_URL.into()),
+            Cluster::Custom(rpc_url(), ws_url()),
             Rc::new(NullSigner::new(&Pubkey::new_unique())),
             CommitmentConfig::confirmed(),
         ),
     }
 }
 
-pub fn request_airdrop(
+// faucets cap how much a single airdrop request may ask for
+pub const MAX_AIRDROP_LAMPORTS: u64 = 2 * util::LAMPORTS_PER_SOL;
+pub const AIRDROP_RETRIES: u32 = 5;
+pub const AIRDROP_BACKOFF_MS: u64 = 2_000;
+
+fn is_rate_limited(err: &ClientError) -> bool {
+    let message = err.to_string().to_lowercase();
+    message.contains("429") || message.contains("rate limit") || message.contains("limit reached")
+}
+
+// only rate limits and transport failures can succeed on a later attempt, anything else (a bad
+// request, a cluster without a faucet) fails the same way every time
+fn is_retryable(err: &ClientError) -> bool {
+    match err.kind() {
+        ClientErrorKind::Io(_) => true,
+        ClientErrorKind::Reqwest(err) => {
+            err.is_timeout()
+                || err.is_connect()
+                || err.status().map_or(false, |status| {
+                    status.as_u16() == 429 || status.is_server_error()
+                })
+        }
+        _ => is_rate_limited(err),
+    }
+}
+
+fn request_airdrop_chunk(
     rpc_client: &RpcClient,
     pub_key: &Pubkey,
-    amount_sol: u64,
-) -> Result<Signature, Box<dyn Error>> {
-    let amount = amount_sol * util::LAMPORTS_PER_SOL;
-    let sig = rpc_client.request_airdrop(pub_key, amount)?;
+    lamports: u64,
+) -> Result<Signature> {
+    let mut attempt = 0;
+    loop {
+        match rpc_client.request_airdrop(pub_key, lamports) {
+            Ok(sig) => {
+                let latest = rpc_client.get_latest_blockhash()?;
+                rpc_client.confirm_transaction_with_spinner(
+                    &sig,
+                    &latest,
+                    CommitmentConfig::finalized(),
+                )?;
 
-    let latest = rpc_client.get_latest_blockhash()?;
-    rpc_client.confirm_transaction_with_spinner(&sig, &latest, CommitmentConfig::finalized())?;
-
-    Ok(sig)
+                return Ok(sig);
+            }
+            Err(err) if attempt < AIRDROP_RETRIES && is_retryable(&err) => {
+                attempt += 1;
+                let backoff = AIRDROP_BACKOFF_MS * 2u64.pow(attempt - 1);
+                if is_rate_limited(&err) {
+                    println!("faucet rate limit hit, retrying in {} ms...", backoff);
+                } else {
+                    println!("airdrop failed ({}), retrying in {} ms...", err, backoff);
+                }
+                thread::sleep(Duration::from_millis(backoff));
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
 }
 
-pub fn check_airdrop_users() -> Result<(), Box<dyn Error>> {
-    let signer = Keypair::from_base58_string(env::var("SIGNER_PRIV")?.as_ref());
-    let partner0 = Keypair::from_base58_string(env::var("U_PARTNER0_PRIV")?.as_ref());
-    let partner1 = Keypair::from_base58_string(env::var("U_PARTNER1_PRIV")?.as_ref());
+// requests `amount` in chunks the faucet accepts, retrying each chunk with backoff
+pub fn request_airdrop(
+    rpc_client: &RpcClient,
+    pub_key: &Pubkey,
+    amount: Amount,
+) -> Result<Vec<Signature>> {
+    let mut remaining = amount.lamports();
+    let mut sigs = Vec::new();
 
-    let signer_pub = signer.pubkey().clone();
-    let partner0_pub = partner0.pubkey().clone();
-    let partner1_pub = partner1.pubkey().clone();
+    while remaining > 0 {
+        let chunk = remaining.min(MAX_AIRDROP_LAMPORTS);
+        sigs.push(request_airdrop_chunk(rpc_client, pub_key, chunk)?);
+        remaining -= chunk;
+    }
 
-    let rpc_client = RpcClient::new(RPC_URL);
+    Ok(sigs)
+}
 
-    let balance = rpc_client.get_balance(&signer_pub)?;
-    if balance < 2 * util::LAMPORTS_PER_SOL {
-        println!("{:?} balance less than 2 lamps: {:?}", signer_pub, balance);
-        println!("requesting airdrop...");
-        request_airdrop(&rpc_client, &signer_pub, 2)?;
+// airdrops whatever is missing for `pub_key` to hold at least `target`
+pub fn airdrop_until_balance(
+    rpc_client: &RpcClient,
+    pub_key: &Pubkey,
+    target: Amount,
+) -> Result<Vec<Signature>> {
+    let balance = rpc_client.get_balance(pub_key)?;
+    let missing = Amount::from_lamports(target.lamports().saturating_sub(balance));
 
-        let balance = rpc_client.get_balance(&signer_pub)?;
-        println!("{:?} new balance: {:?}", signer_pub, balance);
-    }
+    request_airdrop(rpc_client, pub_key, missing)
+}
+
+pub fn ensure_funded(
+    rpc_client: &RpcClient,
+    pub_keys: &[Pubkey],
+    min_balance: Amount,
+) -> Result<()> {
+    for pub_key in pub_keys {
+        let balance = rpc_client.get_balance(pub_key)?;
+        if balance >= min_balance.lamports() {
+            println!("{:?} balance: {}", pub_key, Amount::from_lamports(balance));
+            continue;
+        }
 
-    let balance = rpc_client.get_balance(&partner0_pub)?;
-    if balance < 2 * util::LAMPORTS_PER_SOL {
         println!(
-            "{:?} balance less than 2 lamps: {:?}",
-            partner0_pub, balance
+            "{:?} balance less than {}: {}",
+            pub_key,
+            min_balance,
+            Amount::from_lamports(balance)
         );
         println!("requesting airdrop...");
-        request_airdrop(&rpc_client, &partner0_pub, 2)?;
-
-        let balance = rpc_client.get_balance(&partner0_pub)?;
-        println!("{:?} new balance: {:?}", partner0_pub, balance);
-    }
+        airdrop_until_balance(rpc_client, pub_key, min_balance)?;
 
-    let balance = rpc_client.get_balance(&partner1_pub)?;
-    if balance < 2 * util::LAMPORTS_PER_SOL {
+        let balance = rpc_client.get_balance(pub_key)?;
         println!(
-            "{:?} balance less than 2 lamps: {:?}",
-            partner1_pub, balance
+            "{:?} new balance: {}",
+            pub_key,
+            Amount::from_lamports(balance)
         );
-        println!("requesting airdrop...");
-        request_airdrop(&rpc_client, &partner1_pub, 2)?;
-
-        let balance = rpc_client.get_balance(&partner1_pub)?;
-        println!("{:?} new balance: {:?}", partner1_pub, balance);
     }
 
     Ok(())
 }
+
+// the signer and both test partners from the env file
+pub fn airdrop_users() -> Result<Vec<Pubkey>> {
+    let signer = Keypair::from_base58_string(env::var("SIGNER_PRIV")?.as_ref());
+    let partner0 = Keypair::from_base58_string(env::var("U_PARTNER0_PRIV")?.as_ref());
+    let partner1 = Keypair::from_base58_string(env::var("U_PARTNER1_PRIV")?.as_ref());
+
+    Ok(vec![signer.pubkey(), partner0.pubkey(), partner1.pubkey()])
+}
+
+pub fn check_airdrop_users() -> Result<()> {
+    let rpc_client = RpcClient::new(rpc_url());
+
+    ensure_funded(&rpc_client, &airdrop_users()?, Amount::sol(2))
+}
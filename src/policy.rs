@@ -0,0 +1,159 @@
+use crate::{fee, network, util};
+use anyhow::{bail, Context, Result};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use std::{env, str::FromStr};
+
+// guardrails read from ~/.crypto_wedding_cli_env, or from ~/.crypto_wedding_cli_env.<profile>
+// first when running with --profile:
+// MAX_FEE_LAMPORTS - maximum fee a single transaction may cost, priority fee included
+// MAX_PRIORITY_FEE_LAMPORTS - maximum priority fee a single transaction may pay
+// ALLOWED_PROGRAM_IDS - comma separated program ids this profile may talk to
+// ALLOWED_SIGNERS - comma separated public keys this profile may sign with
+#[derive(Debug, Default)]
+pub struct Policy {
+    pub profile: Option<String>,
+    pub max_fee_lamports: Option<u64>,
+    pub max_priority_fee_lamports: Option<u64>,
+    pub allowed_program_ids: Option<Vec<Pubkey>>,
+    pub allowed_signers: Option<Vec<Pubkey>>,
+}
+
+fn parse_lamports(key: &str) -> Result<Option<u64>> {
+    match env::var(key) {
+        Ok(value) => Ok(Some(
+            value
+                .trim()
+                .parse()
+                .with_context(|| format!("invalid {}", key))?,
+        )),
+        Err(_) => Ok(None),
+    }
+}
+
+fn parse_pubkeys(key: &str) -> Result<Option<Vec<Pubkey>>> {
+    match env::var(key) {
+        Ok(value) => {
+            let pubkeys = value
+                .split(',')
+                .map(str::trim)
+                .filter(|part| !part.is_empty())
+                .map(|part| {
+                    Pubkey::from_str(part).with_context(|| format!("invalid pubkey in {}", key))
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            Ok(Some(pubkeys))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+impl Policy {
+    pub fn from_env() -> Result<Policy> {
+        Ok(Policy {
+            profile: env::var("PROFILE").ok(),
+            max_fee_lamports: parse_lamports("MAX_FEE_LAMPORTS")?,
+            max_priority_fee_lamports: parse_lamports("MAX_PRIORITY_FEE_LAMPORTS")?,
+            allowed_program_ids: parse_pubkeys("ALLOWED_PROGRAM_IDS")?,
+            allowed_signers: parse_pubkeys("ALLOWED_SIGNERS")?,
+        })
+    }
+
+    // names the profile in errors so it is clear which policy refused
+    fn source(&self) -> String {
+        match &self.profile {
+            Some(profile) => format!(" of profile {}", profile),
+            None => String::new(),
+        }
+    }
+
+    pub fn check_airdrop(&self) -> Result<()> {
+        if network::is_mainnet()? {
+            bail!("airdrops are not allowed on mainnet");
+        }
+
+        Ok(())
+    }
+
+    pub fn check_mainnet(&self, acknowledged: bool) -> Result<()> {
+        if !acknowledged && network::is_mainnet()? {
+            bail!(
+                "{} is mainnet, pass --i-know-this-is-mainnet to send transactions",
+                network::rpc_url()
+            );
+        }
+
+        Ok(())
+    }
+
+    pub fn check_program_id(&self, program_id: &Pubkey) -> Result<()> {
+        if let Some(allowed) = &self.allowed_program_ids {
+            if !allowed.contains(program_id) {
+                bail!(
+                    "program {} is not in ALLOWED_PROGRAM_IDS{}",
+                    program_id,
+                    self.source()
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn check_signer(&self, signer: &Pubkey) -> Result<()> {
+        if let Some(allowed) = &self.allowed_signers {
+            if !allowed.contains(signer) {
+                bail!(
+                    "signer {} is not in ALLOWED_SIGNERS{}",
+                    signer,
+                    self.source()
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    // `ixs` are the exact instructions about to be sent by `payer`, compute budget included
+    pub fn check_fee(&self, payer: &Pubkey, ixs: &[Instruction]) -> Result<()> {
+        let priority_fee = fee::priority_fee(ixs);
+        if let Some(max_priority_fee_lamports) = self.max_priority_fee_lamports {
+            if priority_fee > max_priority_fee_lamports {
+                bail!(
+                    "priority fee of {} lamports exceeds MAX_PRIORITY_FEE_LAMPORTS{} of {}",
+                    priority_fee,
+                    self.source(),
+                    max_priority_fee_lamports
+                );
+            }
+        }
+
+        if let Some(max_fee_lamports) = self.max_fee_lamports {
+            let rpc_client = RpcClient::new(network::rpc_url());
+            let fee = fee::base_fee(&rpc_client, payer, ixs)? + priority_fee;
+            if fee > max_fee_lamports {
+                bail!(
+                    "transaction fee of {} lamports exceeds MAX_FEE_LAMPORTS{} of {}",
+                    fee,
+                    self.source(),
+                    max_fee_lamports
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    // runs every check that applies to a command which sends transactions signed by `signers`.
+    // fees are checked per transaction by check_fee when it is sent.
+    pub fn check_mutating(&self, acknowledged: bool, signers: &[Pubkey]) -> Result<()> {
+        self.check_mainnet(acknowledged)?;
+        self.check_program_id(&util::program_id())?;
+        for signer in signers {
+            self.check_signer(signer)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{actions, amount::Amount, network, util};
+use anyhow::{bail, Result};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::io::{self, BufRead, Write};
+
+pub struct AccountPreview {
+    pub label: String,
+    pub address: Pubkey,
+    pub lamports: u64,
+}
+
+pub struct Preview {
+    pub action: String,
+    pub accounts: Vec<AccountPreview>,
+    pub rent_recipient: Pubkey,
+    pub outcome: String,
+}
+
+fn account_preview(rpc_client: &RpcClient, label: &str, address: Pubkey) -> Result<AccountPreview> {
+    let lamports = rpc_client.get_balance(&address)?;
+
+    Ok(AccountPreview {
+        label: label.into(),
+        address,
+        lamports,
+    })
+}
+
+pub fn preview_divorce(user: &Pubkey, other: &Pubkey) -> Result<Preview> {
+    let rpc_client = RpcClient::new(network::rpc_url());
+    let wedding = actions::get_wedding_state(user, other)?;
+
+    Ok(Preview {
+        action: "divorce".into(),
+        accounts: vec![
+            account_preview(&rpc_client, "wedding", util::find_wedding_pda(user, other))?,
+            account_preview(&rpc_client, "partner", util::find_partner_pda(user))?,
+            account_preview(&rpc_client, "other partner", util::find_partner_pda(other))?,
+        ],
+        rent_recipient: wedding.creator,
+        outcome: format!(
+            "wedding between {} and {} is dissolved (current status: {:?})",
+            user, other, wedding.status
+        ),
+    })
+}
+
+pub fn preview_cancel_wedding(partner0: &Pubkey, partner1: &Pubkey) -> Result<Preview> {
+    let rpc_client = RpcClient::new(network::rpc_url());
+    let wedding = actions::get_wedding_state(partner0, partner1)?;
+
+    Ok(Preview {
+        action: "cancel".into(),
+        accounts: vec![account_preview(
+            &rpc_client,
+            "wedding",
+            util::find_wedding_pda(partner0, partner1),
+        )?],
+        rent_recipient: wedding.creator,
+        outcome: format!(
+            "wedding account is closed (current status: {:?})",
+            wedding.status
+        ),
+    })
+}
+
+pub fn preview_close_partner(user: &Pubkey, other: &Pubkey) -> Result<Preview> {
+    let rpc_client = RpcClient::new(network::rpc_url());
+    let wedding_status = match actions::get_wedding_state(user, other) {
+        Ok(wedding) => format!("{:?}", wedding.status),
+        Err(_) => "no wedding account".into(),
+    };
+
+    Ok(Preview {
+        action: "close".into(),
+        accounts: vec![
+            account_preview(&rpc_client, "partner", util::find_partner_pda(user))?,
+            account_preview(&rpc_client, "wedding", util::find_wedding_pda(user, other))?,
+        ],
+        rent_recipient: user.clone(),
+        outcome: format!(
+            "partner account is closed (wedding status: {})",
+            wedding_status
+        ),
+    })
+}
+
+pub fn print_preview(preview: &Preview) {
+    println!("---| {} preview |---", preview.action);
+    for account in &preview.accounts {
+        println!(
+            "{}: {:?} ({})",
+            account.label,
+            account.address,
+            Amount::from_lamports(account.lamports)
+        );
+    }
+    println!("reclaimed rent goes to: {:?}", preview.rent_recipient);
+    println!("result: {}", preview.outcome);
+    println!("-----------------------");
+}
+
+// asks the user to type `action` to continue. `yes` skips the prompt, and without it
+// non-interactive sessions are refused rather than left waiting on stdin.
+pub fn confirm_action(action: &str, yes: bool) -> Result<()> {
+    if yes {
+        return Ok(());
+    }
+
+    if !atty::is(atty::Stream::Stdin) {
+        bail!(
+            "refusing to {} without --yes in a non-interactive session",
+            action
+        );
+    }
+
+    print!("type \"{}\" to continue: ", action);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    if answer.trim() != action {
+        bail!("aborted");
+    }
+
+    Ok(())
+}
+
+pub fn confirm(preview: &Preview, yes: bool) -> Result<()> {
+    print_preview(preview);
+
+    confirm_action(&preview.action, yes)
+}
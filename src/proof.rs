@@ -0,0 +1,315 @@
+use crate::{
+    decode::{self, WeddingAccount},
+    history, message, network, util,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use crypto_wedding::state::Status;
+use serde_json::{json, Value};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    account::Account,
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::{keypair::Keypair, Signer},
+};
+use std::str::FromStr;
+
+pub const PROOF_VERSION: u64 = 2;
+const PROOF_HEADER: &str = "crypto-wedding proof";
+
+// payload keys of the wedding and partner accounts, in signing order
+const ACCOUNT_KEYS: [&str; 3] = ["wedding", "partner0Account", "partner1Account"];
+
+fn account_json(address: &Pubkey, account: &Account) -> Value {
+    json!({
+        "address": address.to_string(),
+        "data": base64::encode(&account.data),
+    })
+}
+
+// the signed bytes are built field by field in a fixed order instead of from the json, so they
+// do not depend on how a json library orders keys or formats values
+pub fn payload_bytes(payload: &Value) -> Result<Vec<u8>> {
+    let version = u64_field(payload, "version")?;
+    let mut headers = vec![
+        ("programId", str_field(payload, "programId")?.to_string()),
+        ("cluster", str_field(payload, "cluster")?.to_string()),
+        ("slot", u64_field(payload, "slot")?.to_string()),
+        ("partner0", str_field(payload, "partner0")?.to_string()),
+        ("partner1", str_field(payload, "partner1")?.to_string()),
+    ];
+    for key in ACCOUNT_KEYS {
+        let account = payload
+            .get(key)
+            .ok_or_else(|| anyhow!("proof is missing {}", key))?;
+        headers.push((
+            key,
+            format!(
+                "{} {}",
+                str_field(account, "address")?,
+                str_field(account, "data")?
+            ),
+        ));
+    }
+
+    let transactions = payload
+        .get("transactions")
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow!("proof is missing transactions"))?
+        .iter()
+        .map(|sig| {
+            sig.as_str()
+                .ok_or_else(|| anyhow!("transaction signatures must be strings"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(message::build_payload(
+        &format!("{} v{}", PROOF_HEADER, version),
+        &headers,
+        &transactions.join("\n"),
+    )
+    .into_bytes())
+}
+
+fn check_married(wedding: &crypto_wedding::state::Wedding) -> Result<()> {
+    if wedding.status != Status::Married {
+        bail!(
+            "wedding status is {:?}, only a marriage can be proven",
+            wedding.status
+        );
+    }
+
+    Ok(())
+}
+
+pub fn prove_marriage(signer: &Keypair, other: &Pubkey) -> Result<Value> {
+    let rpc_client = RpcClient::new(network::rpc_url());
+    let signer_pub = signer.pubkey();
+    let (partner0, partner1) = crypto_wedding::util::sort_pubkeys(&signer_pub, other);
+    let (partner0, partner1) = (partner0.clone(), partner1.clone());
+
+    // read in one request so every account is from the same slot
+    let wedding_pda = util::find_wedding_pda(&partner0, &partner1);
+    let addresses = [
+        wedding_pda,
+        util::find_partner_pda(&partner0),
+        util::find_partner_pda(&partner1),
+    ];
+    let response = rpc_client
+        .get_multiple_accounts_with_commitment(&addresses, CommitmentConfig::finalized())?;
+    let slot = response.context.slot;
+    let accounts = addresses
+        .iter()
+        .zip(response.value)
+        .map(|(address, account)| {
+            account.ok_or_else(|| anyhow!("account {} does not exist", address))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    match decode::decode_account(&accounts[0].data)? {
+        WeddingAccount::Wedding(wedding) => check_married(&wedding)?,
+        WeddingAccount::Partner(_) => bail!("{} holds a partner account", wedding_pda),
+    }
+
+    let transactions: Vec<String> = history::get_history(&partner0, &partner1)?
+        .iter()
+        .filter(|fetched| {
+            !fetched.failed() && !fetched.wedding_instructions(&wedding_pda).is_empty()
+        })
+        .map(|fetched| fetched.sig.to_string())
+        .collect();
+
+    let payload = json!({
+        "version": PROOF_VERSION,
+        "programId": util::program_id().to_string(),
+        "cluster": network::cluster_name(),
+        "slot": slot,
+        "partner0": partner0.to_string(),
+        "partner1": partner1.to_string(),
+        "wedding": account_json(&addresses[0], &accounts[0]),
+        "partner0Account": account_json(&addresses[1], &accounts[1]),
+        "partner1Account": account_json(&addresses[2], &accounts[2]),
+        "transactions": transactions,
+    });
+    let signature = signer.sign_message(&payload_bytes(&payload)?);
+
+    Ok(json!({
+        "payload": payload,
+        "signer": signer_pub.to_string(),
+        "signature": signature.to_string(),
+    }))
+}
+
+pub struct VerifiedProof {
+    pub wedding: Pubkey,
+    pub partner0: Pubkey,
+    pub partner1: Pubkey,
+    pub signer: Pubkey,
+    pub slot: u64,
+    pub status: String,
+    pub names: (String, String),
+    pub transactions: Vec<Signature>,
+}
+
+fn str_field<'a>(value: &'a Value, key: &str) -> Result<&'a str> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("proof is missing {}", key))
+}
+
+fn u64_field(value: &Value, key: &str) -> Result<u64> {
+    value
+        .get(key)
+        .and_then(Value::as_u64)
+        .ok_or_else(|| anyhow!("proof is missing {}", key))
+}
+
+fn pubkey_field(value: &Value, key: &str) -> Result<Pubkey> {
+    Pubkey::from_str(str_field(value, key)?).with_context(|| format!("invalid pubkey in {}", key))
+}
+
+// checks the account entry sits at `expected` and returns its decoded data
+fn verified_account(value: &Value, key: &str, expected: &Pubkey) -> Result<WeddingAccount> {
+    let account = value
+        .get(key)
+        .ok_or_else(|| anyhow!("proof is missing {}", key))?;
+    let address = pubkey_field(account, "address")?;
+    if &address != expected {
+        bail!("{} address {} should be {}", key, address, expected);
+    }
+
+    let data = base64::decode(str_field(account, "data")?)?;
+    decode::decode_account(&data)
+}
+
+// verifies a proof without any network access
+pub fn verify_proof(proof: &Value) -> Result<VerifiedProof> {
+    let payload = proof
+        .get("payload")
+        .ok_or_else(|| anyhow!("proof is missing payload"))?;
+    let version = u64_field(payload, "version")?;
+    if version != PROOF_VERSION {
+        bail!(
+            "proof version {} is not supported, expected {}",
+            version,
+            PROOF_VERSION
+        );
+    }
+    let signer = pubkey_field(proof, "signer")?;
+    let signature = Signature::from_str(str_field(proof, "signature")?)?;
+    if !signature.verify(signer.as_ref(), &payload_bytes(payload)?) {
+        bail!("signature does not match the payload and signer");
+    }
+
+    let program_id = pubkey_field(payload, "programId")?;
+    let partner0 = pubkey_field(payload, "partner0")?;
+    let partner1 = pubkey_field(payload, "partner1")?;
+    if signer != partner0 && signer != partner1 {
+        bail!("proof is signed by {} which is not a partner", signer);
+    }
+
+    let (wedding_address, _) = util::find_wedding_pda_with_bump(&partner0, &partner1, &program_id);
+    let wedding = match verified_account(payload, "wedding", &wedding_address)? {
+        WeddingAccount::Wedding(wedding) => wedding,
+        WeddingAccount::Partner(_) => bail!("wedding data holds a partner account"),
+    };
+    if [wedding.partner0, wedding.partner1] != [partner0, partner1]
+        && [wedding.partner1, wedding.partner0] != [partner0, partner1]
+    {
+        bail!("wedding account belongs to different partners");
+    }
+    check_married(&wedding)?;
+
+    let mut names = Vec::new();
+    for (key, user) in [
+        ("partner0Account", &partner0),
+        ("partner1Account", &partner1),
+    ] {
+        let (address, _) = util::find_partner_pda_with_bump(user, &program_id);
+        match verified_account(payload, key, &address)? {
+            WeddingAccount::Partner(partner) => {
+                if &partner.user != user || partner.wedding != wedding_address {
+                    bail!("{} does not belong to {} in this wedding", key, user);
+                }
+                names.push(partner.name);
+            }
+            WeddingAccount::Wedding(_) => bail!("{} holds a wedding account", key),
+        }
+    }
+
+    let transactions = payload
+        .get("transactions")
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow!("proof is missing transactions"))?
+        .iter()
+        .map(|sig| {
+            sig.as_str()
+                .ok_or_else(|| anyhow!("transaction signatures must be strings"))
+                .and_then(|sig| Ok(Signature::from_str(sig)?))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(VerifiedProof {
+        wedding: wedding_address,
+        partner0,
+        partner1,
+        signer,
+        slot: u64_field(payload, "slot")?,
+        status: format!("{:?}", wedding.status),
+        names: (names.remove(0), names.remove(0)),
+        transactions,
+    })
+}
+
+// re-checks a verified proof against the chain: every transaction must be finalized and the
+// accounts are compared with their current on-chain data
+pub fn check_proof_on_chain(proof: &Value, verified: &VerifiedProof) -> Result<()> {
+    let rpc_client = RpcClient::new(network::rpc_url());
+    // the rpc accepts at most 256 signatures per status request
+    for sigs in verified.transactions.chunks(256) {
+        let statuses = rpc_client.get_signature_statuses_with_history(sigs)?.value;
+        for (sig, status) in sigs.iter().zip(statuses) {
+            match status {
+                Some(status) if status.err.is_none() => {}
+                Some(_) => bail!("transaction {} failed on chain", sig),
+                None => bail!("transaction {} was not found on chain", sig),
+            }
+        }
+    }
+    println!(
+        "{} transactions confirmed on chain",
+        verified.transactions.len()
+    );
+
+    let payload = &proof["payload"];
+    for key in ACCOUNT_KEYS {
+        let address = pubkey_field(&payload[key], "address")?;
+        let data = base64::decode(str_field(&payload[key], "data")?)?;
+        match rpc_client
+            .get_account_with_commitment(&address, CommitmentConfig::finalized())?
+            .value
+        {
+            Some(account) if account.data == data => {
+                println!("{}: unchanged since slot {}", key, verified.slot)
+            }
+            Some(_) => println!("{}: changed since slot {}", key, verified.slot),
+            None => println!("{}: closed since slot {}", key, verified.slot),
+        }
+    }
+
+    Ok(())
+}
+
+pub fn print_verified(verified: &VerifiedProof) {
+    println!("---| verified proof |---");
+    println!("wedding: {:?}", verified.wedding);
+    println!("partner0: {:?} ({})", verified.partner0, verified.names.0);
+    println!("partner1: {:?} ({})", verified.partner1, verified.names.1);
+    println!("status: {}", verified.status);
+    println!("slot: {}", verified.slot);
+    println!("signed by: {:?}", verified.signer);
+    println!("transactions: {}", verified.transactions.len());
+    println!("------------------------");
+}
@@ -0,0 +1,57 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use image::Luma;
+use qrcode::{render::svg, render::unicode, QrCode};
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum QrFormat {
+    Terminal,
+    Png,
+    Svg,
+}
+
+// unicode half blocks, inverted so the code scans on dark terminal backgrounds
+pub fn render_terminal(data: &str) -> Result<String> {
+    let code = QrCode::new(data)?;
+
+    Ok(code
+        .render::<unicode::Dense1x2>()
+        .dark_color(unicode::Dense1x2::Light)
+        .light_color(unicode::Dense1x2::Dark)
+        .build())
+}
+
+pub fn render_svg(data: &str) -> Result<String> {
+    let code = QrCode::new(data)?;
+
+    Ok(code.render::<svg::Color>().min_dimensions(200, 200).build())
+}
+
+// the svg without its xml declaration, for embedding inline in html
+pub fn render_svg_fragment(data: &str) -> Result<String> {
+    let svg = render_svg(data)?;
+
+    Ok(match svg.find("<svg") {
+        Some(start) => svg[start..].to_string(),
+        None => svg,
+    })
+}
+
+pub fn save_png(data: &str, path: &Path) -> Result<()> {
+    let code = QrCode::new(data)?;
+    code.render::<Luma<u8>>()
+        .min_dimensions(200, 200)
+        .build()
+        .save(path)?;
+
+    Ok(())
+}
+
+// module grid without a quiet zone as (width, dark modules row by row), for drawing the code
+// into other documents such as certificates
+pub fn modules(data: &str) -> Result<(usize, Vec<bool>)> {
+    let code = QrCode::new(data)?;
+
+    Ok((code.width(), code.to_vec()))
+}
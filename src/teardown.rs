@@ -0,0 +1,151 @@
+use crate::{actions, amount::Amount, network, transfer, util};
+use anyhow::Result;
+use crypto_wedding::state::Status;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::{keypair::Keypair, Signer},
+};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TeardownStep {
+    Divorce,
+    ClosePartner,
+    CancelWedding,
+    Sweep,
+}
+
+impl fmt::Display for TeardownStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TeardownStep::Divorce => write!(f, "divorce"),
+            TeardownStep::ClosePartner => write!(f, "close partner account"),
+            TeardownStep::CancelWedding => write!(f, "cancel wedding"),
+            TeardownStep::Sweep => write!(f, "sweep remaining SOL"),
+        }
+    }
+}
+
+// a married couple has to divorce, which also closes both partner accounts. anything before
+// that can simply be cancelled once the signer's partner account is closed.
+pub fn plan_teardown(user: &Pubkey, other: &Pubkey) -> Vec<TeardownStep> {
+    let has_partner = actions::get_partner_state(user).is_ok();
+    let wedding_status = actions::get_wedding_state(user, other)
+        .ok()
+        .map(|wedding| wedding.status);
+
+    plan_steps(wedding_status, has_partner)
+}
+
+fn plan_steps(wedding_status: Option<Status>, has_partner: bool) -> Vec<TeardownStep> {
+    let mut plan = Vec::new();
+    match wedding_status {
+        Some(Status::Married) => plan.push(TeardownStep::Divorce),
+        Some(_) => {
+            if has_partner {
+                plan.push(TeardownStep::ClosePartner);
+            }
+            plan.push(TeardownStep::CancelWedding);
+        }
+        None => {
+            if has_partner {
+                plan.push(TeardownStep::ClosePartner);
+            }
+        }
+    }
+    plan.push(TeardownStep::Sweep);
+
+    plan
+}
+
+pub fn print_plan(user: &Pubkey, other: &Pubkey, to: &Pubkey, plan: &[TeardownStep]) -> Result<()> {
+    let rpc_client = RpcClient::new(network::rpc_url());
+
+    println!("---| teardown plan |---");
+    println!(
+        "wedding: {:?} ({})",
+        util::find_wedding_pda(user, other),
+        Amount::from_lamports(rpc_client.get_balance(&util::find_wedding_pda(user, other))?)
+    );
+    println!(
+        "partner: {:?} ({})",
+        util::find_partner_pda(user),
+        Amount::from_lamports(rpc_client.get_balance(&util::find_partner_pda(user))?)
+    );
+    println!(
+        "signer: {:?} ({})",
+        user,
+        Amount::from_lamports(rpc_client.get_balance(user)?)
+    );
+    for (i, step) in plan.iter().enumerate() {
+        println!("{}. {}", i + 1, step);
+    }
+    println!("remaining SOL goes to: {:?}", to);
+    println!("-----------------------");
+
+    Ok(())
+}
+
+// runs each planned step, stopping at the first failure. the sweep is priced up front so a
+// failing fee lookup stops the teardown before any account is closed.
+pub fn run_teardown(
+    user: &Keypair,
+    other: &Pubkey,
+    to: &Pubkey,
+    plan: &[TeardownStep],
+) -> Result<Vec<(TeardownStep, Signature)>> {
+    let user_pub = user.pubkey();
+    let rpc_client = RpcClient::new(network::rpc_url());
+    let sweep_fee = transfer::sweep_fee(&rpc_client, &user_pub, to)?;
+    let mut sigs = Vec::new();
+
+    for step in plan {
+        println!("teardown: {}...", step);
+        let sig = match step {
+            TeardownStep::Divorce => Some(actions::divorce(user, other)?),
+            TeardownStep::ClosePartner => Some(actions::close_partner(user, other)?),
+            TeardownStep::CancelWedding => Some(actions::cancel_wedding(user, &user_pub, other)?),
+            TeardownStep::Sweep => transfer::sweep(user, to, sweep_fee)?,
+        };
+
+        if let Some(sig) = sig {
+            sigs.push((*step, sig));
+        }
+    }
+
+    Ok(sigs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TeardownStep::*;
+    use super::*;
+
+    #[test]
+    fn married_couples_divorce() {
+        assert_eq!(plan_steps(Some(Status::Married), true), [Divorce, Sweep]);
+        assert_eq!(plan_steps(Some(Status::Married), false), [Divorce, Sweep]);
+    }
+
+    #[test]
+    fn unmarried_weddings_are_cancelled() {
+        for status in [Status::Created, Status::PartnerAnswered] {
+            assert_eq!(
+                plan_steps(Some(status), true),
+                [ClosePartner, CancelWedding, Sweep]
+            );
+        }
+        assert_eq!(
+            plan_steps(Some(Status::Created), false),
+            [CancelWedding, Sweep]
+        );
+    }
+
+    #[test]
+    fn without_a_wedding_only_the_partner_is_closed() {
+        assert_eq!(plan_steps(None, true), [ClosePartner, Sweep]);
+        assert_eq!(plan_steps(None, false), [Sweep]);
+    }
+}
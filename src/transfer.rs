@@ -0,0 +1,142 @@
+use crate::{amount::Amount, fee, network, policy::Policy, util};
+use anyhow::{bail, Result};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::{keypair::Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+
+// signs and sends `ixs` paid by `signer` once the fee policy accepts them. callers start `ixs`
+// with fee::priority_fee_ixs so the priority fee is both paid and checked.
+pub fn send_transaction(
+    rpc_client: &RpcClient,
+    signer: &Keypair,
+    ixs: &[Instruction],
+) -> Result<Signature> {
+    let signer_pub = signer.pubkey();
+    Policy::from_env()?.check_fee(&signer_pub, ixs)?;
+
+    let latest = rpc_client.get_latest_blockhash()?;
+    let tx = Transaction::new_signed_with_payer(ixs, Some(&signer_pub), &[signer], latest);
+
+    Ok(rpc_client.send_and_confirm_transaction_with_spinner(&tx)?)
+}
+
+// how much to send `recipient`. with `top_up` the amount is a target balance and only the
+// shortfall is sent, otherwise the full amount is sent.
+pub fn amount_to_send(
+    rpc_client: &RpcClient,
+    recipient: &Pubkey,
+    amount: Amount,
+    top_up: bool,
+) -> Result<Amount> {
+    if !top_up {
+        return Ok(amount);
+    }
+
+    let balance = rpc_client.get_balance(recipient)?;
+
+    Ok(Amount::from_lamports(
+        amount.lamports().saturating_sub(balance),
+    ))
+}
+
+// transfers SOL from `signer` to every recipient in a single transaction with an optional memo.
+// returns none when every recipient is already topped up.
+pub fn fund(
+    signer: &Keypair,
+    recipients: &[Pubkey],
+    amount: Amount,
+    top_up: bool,
+    memo: Option<&str>,
+) -> Result<Option<Signature>> {
+    let rpc_client = RpcClient::new(network::rpc_url());
+    let signer_pub = signer.pubkey();
+
+    let mut ixs = Vec::new();
+    for recipient in recipients {
+        let to_send = amount_to_send(&rpc_client, recipient, amount, top_up)?;
+        if to_send.lamports() == 0 {
+            println!("{:?} already holds at least {}", recipient, amount);
+            continue;
+        }
+
+        println!("sending {} to {:?}", to_send, recipient);
+        ixs.push(system_instruction::transfer(
+            &signer_pub,
+            recipient,
+            to_send.lamports(),
+        ));
+    }
+
+    if ixs.is_empty() {
+        return Ok(None);
+    }
+
+    if let Some(memo) = memo {
+        ixs.push(spl_memo::build_memo(memo.as_bytes(), &[&signer_pub]));
+    }
+    let mut all_ixs = fee::priority_fee_ixs()?;
+    all_ixs.append(&mut ixs);
+
+    Ok(Some(send_transaction(&rpc_client, signer, &all_ixs)?))
+}
+
+// sends a memo signed by `signer` in a transaction which also lists `reference`, so the memo
+// shows up in the history of that account. the memo program only accepts signer accounts, so
+// the reference is added with a zero lamport transfer instead.
+pub fn send_memo(signer: &Keypair, reference: &Pubkey, memo: &str) -> Result<Signature> {
+    let rpc_client = RpcClient::new(network::rpc_url());
+    let signer_pub = signer.pubkey();
+
+    let mut ixs = fee::priority_fee_ixs()?;
+    ixs.push(system_instruction::transfer(&signer_pub, reference, 0));
+    ixs.push(spl_memo::build_memo(memo.as_bytes(), &[&signer_pub]));
+    let bytes_left = util::tx_bytes_left(&signer_pub, &ixs);
+    if bytes_left < 0 {
+        bail!(
+            "memo is {} bytes too long for a single transaction",
+            -bytes_left
+        );
+    }
+
+    send_transaction(&rpc_client, signer, &ixs)
+}
+
+// the sweep transfer for `lamports`, the amount does not change the fee
+fn sweep_ixs(signer: &Pubkey, to: &Pubkey, lamports: u64) -> Result<Vec<Instruction>> {
+    let mut ixs = fee::priority_fee_ixs()?;
+    ixs.push(system_instruction::transfer(signer, to, lamports));
+
+    Ok(ixs)
+}
+
+// fee for the sweep transaction, which does not depend on the amount swept
+pub fn sweep_fee(rpc_client: &RpcClient, signer: &Pubkey, to: &Pubkey) -> Result<u64> {
+    fee::transaction_fee(rpc_client, signer, &sweep_ixs(signer, to, 0)?)
+}
+
+// sends everything `signer` holds, minus `fee` from sweep_fee, to `to`. the fee is passed in so
+// callers can price the sweep before running anything it depends on.
+pub fn sweep(signer: &Keypair, to: &Pubkey, fee: u64) -> Result<Option<Signature>> {
+    let rpc_client = RpcClient::new(network::rpc_url());
+    let signer_pub = signer.pubkey();
+
+    let balance = rpc_client.get_balance(&signer_pub)?;
+    if balance <= fee {
+        return Ok(None);
+    }
+
+    println!(
+        "sweeping {} to {:?}",
+        Amount::from_lamports(balance - fee),
+        to
+    );
+    let ixs = sweep_ixs(&signer_pub, to, balance - fee)?;
+
+    Ok(Some(send_transaction(&rpc_client, signer, &ixs)?))
+}
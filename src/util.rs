@@ -1,33 +1,80 @@
 use crate::network;
 use anchor_client::Program;
-use solana_sdk::{pubkey::Pubkey, signature::Signature, signer::keypair::Keypair};
+use solana_sdk::{
+    entrypoint::MAX_PERMITTED_DATA_INCREASE,
+    instruction::Instruction,
+    message::Message,
+    packet::PACKET_DATA_SIZE,
+    pubkey::{ParsePubkeyError, Pubkey},
+    signature::Signature,
+    signer::keypair::Keypair,
+};
+use std::{env, str::FromStr};
 
 pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
 
+// discriminator + creator + partner0 + partner1 + status
+pub const WEDDING_ACCOUNT_SIZE: usize = 8 + 32 + 32 + 32 + 1;
+
+// discriminator + wedding + user + name + vows + answer, strings are length prefixed
+pub fn partner_account_size(name: &str, vows: &str) -> usize {
+    8 + 32 + 32 + (4 + name.len()) + (4 + vows.len()) + 1
+}
+
+// bytes left before the partner account for `name` and `vows` outgrows what the program can
+// allocate. the account is created through a cpi and resized with realloc, and the runtime lets
+// either grow an account by at most MAX_PERMITTED_DATA_INCREASE bytes per instruction.
+// `current_size` is the size of the existing account, 0 when it is being set up.
+pub fn partner_space_left(current_size: usize, name: &str, vows: &str) -> i64 {
+    (current_size + MAX_PERMITTED_DATA_INCREASE) as i64 - partner_account_size(name, vows) as i64
+}
+
+// PROGRAM_ID (set in ~/.crypto_wedding_cli_env or with --program-id) selects a fork or local
+// deployment of the program, defaulting to the id crypto_wedding was built with
+pub fn parse_program_id() -> Result<Pubkey, ParsePubkeyError> {
+    match env::var("PROGRAM_ID") {
+        Ok(program_id) => Pubkey::from_str(program_id.trim()),
+        Err(_) => Ok(crypto_wedding::ID),
+    }
+}
+
+pub fn program_id() -> Pubkey {
+    parse_program_id().expect("PROGRAM_ID is not a valid public key")
+}
+
 pub fn get_crypto_wedding(signer: Option<&Keypair>) -> Program {
     let client = network::get_client(signer);
-    client.program(crypto_wedding::ID)
+    client.program(program_id())
 }
 
-pub fn find_wedding_pda(partner_a: &Pubkey, partner_b: &Pubkey) -> Pubkey {
+pub fn find_wedding_pda_with_bump(
+    partner_a: &Pubkey,
+    partner_b: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
     let (partner0, partner1) = crypto_wedding::util::sort_pubkeys(partner_a, partner_b);
-    let (pda, _) = Pubkey::find_program_address(
+    Pubkey::find_program_address(
         &[
             b"wedding".as_ref(),
             &partner0.to_bytes(),
             &partner1.to_bytes(),
         ],
-        &crypto_wedding::ID,
-    );
+        program_id,
+    )
+}
+
+pub fn find_wedding_pda(partner_a: &Pubkey, partner_b: &Pubkey) -> Pubkey {
+    let (pda, _) = find_wedding_pda_with_bump(partner_a, partner_b, &program_id());
 
     pda
 }
 
+pub fn find_partner_pda_with_bump(partner: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"partner".as_ref(), &partner.to_bytes()], program_id)
+}
+
 pub fn find_partner_pda(partner: &Pubkey) -> Pubkey {
-    let (pda, _) = Pubkey::find_program_address(
-        &[b"partner".as_ref(), &partner.to_bytes()],
-        &crypto_wedding::ID,
-    );
+    let (pda, _) = find_partner_pda_with_bump(partner, &program_id());
 
     pda
 }
@@ -42,3 +89,28 @@ pub fn get_tx_link(sig: &Signature, cluster: Option<&str>) -> String {
         }
     }
 }
+
+// bytes remaining before a transaction made of `ixs` hits the packet size limit. negative when
+// the transaction would be rejected.
+pub fn tx_bytes_left(payer: &Pubkey, ixs: &[Instruction]) -> i64 {
+    let message = Message::new(ixs, Some(payer));
+    let num_signatures = message.header.num_required_signatures as usize;
+    // signatures are prefixed by a compact length which is a single byte for < 128 signatures
+    let size = 1 + num_signatures * 64 + message.serialize().len();
+
+    PACKET_DATA_SIZE as i64 - size as i64
+}
+
+pub fn get_account_link(pubkey: &Pubkey, cluster: Option<&str>) -> String {
+    match cluster {
+        None => {
+            format!("https://explorer.solana.com/address/{}", pubkey)
+        }
+        Some(network) => {
+            format!(
+                "https://explorer.solana.com/address/{}?cluster={}",
+                pubkey, network
+            )
+        }
+    }
+}
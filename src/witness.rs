@@ -0,0 +1,180 @@
+use crate::{
+    decode::{self, WeddingAccount},
+    history,
+    message::{self, SignedMessage},
+    network, transfer, util,
+};
+use anyhow::{anyhow, bail, Result};
+use serde_json::Value;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::{keypair::Keypair, Signer},
+};
+
+pub const ATTESTATION_HEADER: &str = "crypto wedding witness attestation";
+// submitted attestations are stored as this prefix followed by the signed message json
+pub const ATTESTATION_MEMO_PREFIX: &str = "crypto-wedding witness ";
+
+pub const DEFAULT_STATEMENT: &str = "I witnessed this wedding.";
+
+// signs `statement` together with the wedding state as read at the returned slot
+pub fn attest(
+    witness: &Keypair,
+    partner0: &Pubkey,
+    partner1: &Pubkey,
+    statement: &str,
+) -> Result<SignedMessage> {
+    let rpc_client = RpcClient::new(network::rpc_url());
+    let address = util::find_wedding_pda(partner0, partner1);
+    let response =
+        rpc_client.get_account_with_commitment(&address, CommitmentConfig::finalized())?;
+    let account = response
+        .value
+        .ok_or_else(|| anyhow!("wedding {} does not exist", address))?;
+    let wedding = match decode::decode_account(&account.data)? {
+        WeddingAccount::Wedding(wedding) => wedding,
+        WeddingAccount::Partner(_) => bail!("{} is not a wedding account", address),
+    };
+
+    let payload = message::build_payload(
+        ATTESTATION_HEADER,
+        &[
+            ("program", util::program_id().to_string()),
+            ("witness", witness.pubkey().to_string()),
+            ("wedding", address.to_string()),
+            ("partner0", wedding.partner0.to_string()),
+            ("partner1", wedding.partner1.to_string()),
+            ("status", format!("{:?}", wedding.status)),
+            ("slot", response.context.slot.to_string()),
+        ],
+        statement,
+    );
+
+    Ok(SignedMessage::sign(witness, payload))
+}
+
+pub fn submit(witness: &Keypair, attestation: &SignedMessage) -> Result<Signature> {
+    let wedding = attestation.header_pubkey("wedding")?;
+    let memo = format!(
+        "{}{}",
+        ATTESTATION_MEMO_PREFIX,
+        serde_json::to_string(&attestation.to_json())?
+    );
+
+    transfer::send_memo(witness, &wedding, &memo)
+}
+
+pub fn verify_attestation(attestation: &SignedMessage, wedding: &Pubkey) -> Result<()> {
+    if attestation.kind() != ATTESTATION_HEADER {
+        bail!("not a witness attestation: {}", attestation.kind());
+    }
+    attestation.verify()?;
+
+    if attestation.header_pubkey("program")? != util::program_id() {
+        bail!("attestation was signed for a different program");
+    }
+    if attestation.header_pubkey("witness")? != attestation.signer {
+        bail!("witness header does not match the signing key");
+    }
+    if &attestation.header_pubkey("wedding")? != wedding {
+        bail!("attestation is for a different wedding");
+    }
+
+    Ok(())
+}
+
+pub struct ListedAttestation {
+    pub sig: Signature,
+    pub block_time: Option<i64>,
+    pub attestation: SignedMessage,
+    // none when the attestation is valid and the witness signed the transaction
+    pub problem: Option<String>,
+}
+
+fn parse_memo(memo: &str) -> Option<Result<SignedMessage>> {
+    let json = memo.strip_prefix(ATTESTATION_MEMO_PREFIX)?;
+
+    Some(
+        serde_json::from_str::<Value>(json)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| SignedMessage::from_json(&json)),
+    )
+}
+
+// attestations submitted to the wedding's history, oldest first
+pub fn list_attestations(partner0: &Pubkey, partner1: &Pubkey) -> Result<Vec<ListedAttestation>> {
+    let wedding = util::find_wedding_pda(partner0, partner1);
+    let mut listed = Vec::new();
+
+    for fetched in history::get_history(partner0, partner1)? {
+        if fetched.failed() {
+            continue;
+        }
+
+        for memo in fetched.memos() {
+            let attestation = match parse_memo(&memo) {
+                Some(Ok(attestation)) => attestation,
+                Some(Err(err)) => {
+                    println!(
+                        "skipping unreadable attestation in {}: {}",
+                        fetched.sig, err
+                    );
+                    continue;
+                }
+                None => continue,
+            };
+
+            let problem = match verify_attestation(&attestation, &wedding) {
+                Err(err) => Some(err.to_string()),
+                Ok(_) if !fetched.signers().contains(&attestation.signer) => {
+                    Some("submitted by someone other than the witness".into())
+                }
+                Ok(_) => None,
+            };
+            listed.push(ListedAttestation {
+                sig: fetched.sig,
+                block_time: fetched.block_time,
+                attestation,
+                problem,
+            });
+        }
+    }
+
+    Ok(listed)
+}
+
+pub fn print_attestations(wedding: &Pubkey, listed: &[ListedAttestation]) {
+    println!("---| witnesses |---");
+    println!("wedding: {:?}", wedding);
+    if listed.is_empty() {
+        println!("no attestations found");
+    }
+    for entry in listed {
+        let headers = entry.attestation.headers();
+        let header = |key: &str| headers.get(key).cloned().unwrap_or_default();
+
+        println!();
+        println!(
+            "witness: {:?}{}",
+            entry.attestation.signer,
+            match &entry.problem {
+                Some(problem) => format!(" INVALID: {}", problem),
+                None => String::new(),
+            }
+        );
+        println!("  status: {} at slot {}", header("status"), header("slot"));
+        println!("  statement: {:?}", entry.attestation.body());
+        println!(
+            "  submitted: {}",
+            history::format_block_time(entry.block_time)
+        );
+        println!(
+            "  tx: {}",
+            util::get_tx_link(&entry.sig, network::explorer_cluster().as_deref())
+        );
+    }
+    println!("-------------------");
+}